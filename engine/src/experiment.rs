@@ -0,0 +1,329 @@
+//! Batch experiments over the simulation.
+//!
+//! An experiment runs the simulation for every cell of a grid of strategies, iteration
+//! counts and epsilons, once for each seed. Each cell then reports summary statistics
+//! (mean, standard deviation and percentiles) across its runs, both of how long a run
+//! took and of where the simulated learners ended up, going by the same learning
+//! outcome metrics as a strategy comparison (see `metrics::StrategyMetrics`). The
+//! report can be written as CSV or JSON.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Serialize;
+use types::config::EngineConfig;
+use types::error::NeuroNudgeError;
+
+use crate::learner_model::LearnerModelKind;
+use crate::metrics::StrategyMetrics;
+use crate::simulate::{self, SimulationOptions};
+
+/// ExperimentGrid
+/// The values to run the simulation with. Every combination of strategy, iteration
+/// count and epsilon is a cell, and each cell is run once per seed.
+#[derive(Debug, Clone)]
+pub struct ExperimentGrid {
+    pub strategies: Vec<u8>,
+    pub iterations: Vec<u32>,
+    pub epsilons: Vec<f32>,
+    pub seeds: Vec<u64>,
+    /// How the simulated learners answer questions. Each run starts a fresh model.
+    pub learner_model: LearnerModelKind,
+}
+
+/// Statistics
+/// Summary statistics of some measurement across the runs of a cell.
+#[derive(Debug, Clone, Serialize)]
+pub struct Statistics {
+    pub mean: f64,
+    /// The sample standard deviation, or 0 for a single run.
+    pub std_dev: f64,
+    pub min: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p95: f64,
+    pub max: f64,
+}
+
+/// ExperimentCell
+/// The results of one cell of the grid.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExperimentCell {
+    pub strategy: u8,
+    pub iterations: u32,
+    pub epsilon: f32,
+    pub runs: usize,
+    pub runtime_ms: Statistics,
+    /// How many levels each learner reached Full mastery in.
+    pub levels_reaching_full_mastery: Statistics,
+    /// The hardest sustained level, from 1 (VeryEasy) to 8 (Grandmaster) or 0 if none.
+    pub highest_level_sustained: Statistics,
+    /// The area under the learners' q value curves.
+    pub q_value_auc: Statistics,
+}
+
+/// The format of an experiment report, which is known from the report file's extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Csv,
+    Json,
+}
+
+impl ReportFormat {
+    /// Get the format of a report file from its extension, so that an unsupported
+    /// file can be rejected before the experiment is run.
+    pub fn from_path(path: &Path) -> Result<ReportFormat, NeuroNudgeError> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("csv") => Ok(ReportFormat::Csv),
+            Some("json") => Ok(ReportFormat::Json),
+            _ => Err(NeuroNudgeError::Serialization(format!(
+                "{} is neither a .csv nor a .json file",
+                path.display()
+            ))),
+        }
+    }
+}
+
+/// The measurements of a cell, in the order that they appear in the CSV report.
+const CSV_MEASUREMENTS: [&str; 4] = [
+    "runtime_ms",
+    "levels_reaching_full_mastery",
+    "highest_level_sustained",
+    "q_value_auc",
+];
+
+impl Statistics {
+    /// Summarise some samples. All statistics are 0 if there are no samples.
+    pub fn from_samples(samples: &[f64]) -> Statistics {
+        if samples.is_empty() {
+            return Statistics {
+                mean: 0.0,
+                std_dev: 0.0,
+                min: 0.0,
+                p50: 0.0,
+                p90: 0.0,
+                p95: 0.0,
+                max: 0.0,
+            };
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        let count = sorted.len() as f64;
+        let mean = sorted.iter().sum::<f64>() / count;
+        let std_dev = if sorted.len() > 1 {
+            let variance = sorted.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (count - 1.0);
+            variance.sqrt()
+        } else {
+            0.0
+        };
+
+        Statistics {
+            mean,
+            std_dev,
+            min: sorted[0],
+            p50: percentile(&sorted, 0.5),
+            p90: percentile(&sorted, 0.9),
+            p95: percentile(&sorted, 0.95),
+            max: sorted[sorted.len() - 1],
+        }
+    }
+
+    fn to_csv_fields(&self) -> [f64; 7] {
+        [
+            self.mean,
+            self.std_dev,
+            self.min,
+            self.p50,
+            self.p90,
+            self.p95,
+            self.max,
+        ]
+    }
+}
+
+/// Get a percentile of some sorted samples, interpolating linearly between the two
+/// nearest samples.
+fn percentile(sorted: &[f64], fraction: f64) -> f64 {
+    let rank = fraction * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// Run every cell of the grid once per seed, returning the results of each cell.
+/// Simulation results aren't written to file, so the runtime is of the simulation alone.
+pub fn run_experiment(
+    grid: &ExperimentGrid,
+    config: &EngineConfig,
+) -> Result<Vec<ExperimentCell>, NeuroNudgeError> {
+    let mut cells = vec![];
+
+    for &strategy in &grid.strategies {
+        for &iterations in &grid.iterations {
+            for &epsilon in &grid.epsilons {
+                let options = SimulationOptions {
+                    iterations,
+                    epsilon,
+                    config: config.clone(),
+                    output_path: None,
+                };
+
+                let mut runtimes = vec![];
+                let mut levels_reaching_full_mastery = vec![];
+                let mut highest_levels_sustained = vec![];
+                let mut q_value_aucs = vec![];
+
+                for &seed in &grid.seeds {
+                    let mut rng = StdRng::seed_from_u64(seed);
+                    let time = std::time::Instant::now();
+                    let summary = simulate::run_simulation_strategy(
+                        strategy,
+                        &options,
+                        grid.learner_model.build().as_mut(),
+                        &mut rng,
+                    )?;
+                    runtimes.push(time.elapsed().as_secs_f64() * 1000.0);
+
+                    let outcomes = StrategyMetrics::new(strategy, &summary.learner_metrics);
+                    levels_reaching_full_mastery.push(outcomes.levels_reaching_full_mastery);
+                    highest_levels_sustained.push(outcomes.highest_level_sustained);
+                    q_value_aucs.push(outcomes.q_value_auc);
+                }
+
+                let cell = ExperimentCell {
+                    strategy,
+                    iterations,
+                    epsilon,
+                    runs: grid.seeds.len(),
+                    runtime_ms: Statistics::from_samples(&runtimes),
+                    levels_reaching_full_mastery: Statistics::from_samples(
+                        &levels_reaching_full_mastery,
+                    ),
+                    highest_level_sustained: Statistics::from_samples(&highest_levels_sustained),
+                    q_value_auc: Statistics::from_samples(&q_value_aucs),
+                };
+
+                println!(
+                    ">> Strategy {}, {} iterations, epsilon {}: {:.1}ms mean runtime, {:.2} levels mastered",
+                    strategy,
+                    iterations,
+                    epsilon,
+                    cell.runtime_ms.mean,
+                    cell.levels_reaching_full_mastery.mean
+                );
+
+                cells.push(cell);
+            }
+        }
+    }
+
+    Ok(cells)
+}
+
+/// Format the results of an experiment as CSV, with one row per cell.
+pub fn to_csv(cells: &[ExperimentCell]) -> String {
+    let mut header = vec![
+        "strategy".to_string(),
+        "iterations".to_string(),
+        "epsilon".to_string(),
+        "runs".to_string(),
+    ];
+    for measurement in CSV_MEASUREMENTS {
+        for statistic in ["mean", "std_dev", "min", "p50", "p90", "p95", "max"] {
+            header.push(format!("{}_{}", measurement, statistic));
+        }
+    }
+
+    let mut csv = header.join(",") + "\n";
+    for cell in cells {
+        let mut row = vec![
+            cell.strategy.to_string(),
+            cell.iterations.to_string(),
+            cell.epsilon.to_string(),
+            cell.runs.to_string(),
+        ];
+        for statistics in [
+            &cell.runtime_ms,
+            &cell.levels_reaching_full_mastery,
+            &cell.highest_level_sustained,
+            &cell.q_value_auc,
+        ] {
+            row.extend(statistics.to_csv_fields().iter().map(|v| v.to_string()));
+        }
+        csv += &(row.join(",") + "\n");
+    }
+
+    csv
+}
+
+/// Write the results of an experiment to a file in the given format.
+pub fn write_report(
+    path: &Path,
+    format: ReportFormat,
+    cells: &[ExperimentCell],
+) -> Result<(), NeuroNudgeError> {
+    let report = match format {
+        ReportFormat::Csv => to_csv(cells),
+        ReportFormat::Json => serde_json::to_string_pretty(cells)?,
+    };
+
+    let mut report_file = File::create(path)?;
+    write!(report_file, "{}", report)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn statistics_of_fixed_samples() {
+        let statistics = Statistics::from_samples(&[4.0, 1.0, 3.0, 2.0, 5.0]);
+
+        assert_close(statistics.mean, 3.0);
+        // The sample variance is (4 + 1 + 0 + 1 + 4) / 4.
+        assert_close(statistics.std_dev, 2.5_f64.sqrt());
+        assert_close(statistics.min, 1.0);
+        assert_close(statistics.p50, 3.0);
+        assert_close(statistics.p90, 4.6);
+        assert_close(statistics.p95, 4.8);
+        assert_close(statistics.max, 5.0);
+    }
+
+    #[test]
+    fn percentiles_interpolate_between_samples() {
+        let sorted = [10.0, 20.0];
+        assert_close(percentile(&sorted, 0.0), 10.0);
+        assert_close(percentile(&sorted, 0.5), 15.0);
+        assert_close(percentile(&sorted, 0.9), 19.0);
+        assert_close(percentile(&sorted, 1.0), 20.0);
+    }
+
+    #[test]
+    fn statistics_of_one_sample_have_no_spread() {
+        let statistics = Statistics::from_samples(&[7.0]);
+        assert_close(statistics.mean, 7.0);
+        assert_close(statistics.std_dev, 0.0);
+        assert_close(statistics.p50, 7.0);
+        assert_close(statistics.p95, 7.0);
+    }
+
+    #[test]
+    fn statistics_of_no_samples_are_zero() {
+        let statistics = Statistics::from_samples(&[]);
+        assert_close(statistics.mean, 0.0);
+        assert_close(statistics.max, 0.0);
+    }
+}
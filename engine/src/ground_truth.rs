@@ -0,0 +1,305 @@
+//! Ground truth evaluation of the engine's recommendations.
+//!
+//! A simulated learner's true chance of success at each difficulty level comes from
+//! their `LearnerModel`. From that, each learner has a zone of proximal development in
+//! the module - the levels that are neither so easy that they learn little, nor so hard
+//! that they mostly fail. The zone is worked out again for every recommendation, from
+//! the learner's latent ability at that point, so it moves as the learner learns but
+//! never depends on the q table that is being scored. Every lesson that
+//! `epsilon_greedy_action` recommends is scored against the zone as too easy, on target
+//! or too hard, with a regret of how many levels it is from the zone. A `RecommendationCurve` then tracks the accuracy and regret of a
+//! strategy over the iterations of a simulation.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use serde::Serialize;
+use types::content::{DifficultyLevel, Lesson, LessonCatalogue, DIFFICULTY_LEVELS};
+use types::engine::Strategy;
+use types::error::NeuroNudgeError;
+use types::learner::Learner;
+
+use crate::learner_model::LearnerModel;
+
+/// A level where the learner's chance of success is above this is too easy.
+const TOO_EASY_PROBABILITY: f64 = 0.85;
+/// A level where the learner's chance of success is below this is too hard.
+const TOO_HARD_PROBABILITY: f64 = 0.5;
+
+/// RecommendationFit
+/// How a recommended lesson's difficulty compares to the learner's zone of proximal
+/// development.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum RecommendationFit {
+    TooEasy,
+    OnTarget,
+    TooHard,
+}
+
+/// ZoneOfProximalDevelopment
+/// The range of difficulty levels in a module that a learner should be working at.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ZoneOfProximalDevelopment {
+    pub module_id: String,
+    pub lowest: DifficultyLevel,
+    pub highest: DifficultyLevel,
+}
+
+/// RecommendationPoint
+/// The scores of every learner's recommendation in one iteration of a simulation.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecommendationPoint {
+    pub iteration: u32,
+    pub too_easy: u32,
+    pub on_target: u32,
+    pub too_hard: u32,
+    /// The share of this iteration's recommendations that were on target.
+    pub accuracy: f64,
+    /// The share of all recommendations so far that were on target.
+    pub cumulative_accuracy: f64,
+    /// The total regret of all recommendations so far, averaged over the learners.
+    pub cumulative_regret: f64,
+}
+
+/// RecommendationCurve
+/// The accuracy and regret of a simulation's recommendations after every iteration.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RecommendationCurve {
+    pub points: Vec<RecommendationPoint>,
+    #[serde(skip)]
+    total_recommendations: u32,
+    #[serde(skip)]
+    total_on_target: u32,
+    #[serde(skip)]
+    total_regret: u32,
+}
+
+fn level_index(difficulty_level: &DifficultyLevel) -> usize {
+    DIFFICULTY_LEVELS
+        .iter()
+        .position(|d| d == difficulty_level)
+        .unwrap_or(0)
+}
+
+impl ZoneOfProximalDevelopment {
+    /// Work out the zone from the learner's chance of success at each level of the
+    /// module, ordered from easiest to hardest. The zone runs from the easiest level
+    /// that isn't too easy to the hardest level that isn't too hard. If every level is
+    /// too easy the zone is the hardest level, and if every level is too hard it is the
+    /// easiest. Returns `None` if there are no levels.
+    pub fn from_success_probabilities(
+        module_id: &str,
+        probabilities: &[(DifficultyLevel, f64)],
+    ) -> Option<ZoneOfProximalDevelopment> {
+        let (easiest, _) = probabilities.first()?;
+        let (hardest, _) = probabilities.last()?;
+
+        let lowest = probabilities
+            .iter()
+            .find(|(_, probability)| *probability <= TOO_EASY_PROBABILITY)
+            .map_or(hardest, |(level, _)| level);
+        let highest = probabilities
+            .iter()
+            .rev()
+            .find(|(_, probability)| *probability >= TOO_HARD_PROBABILITY)
+            .map_or(easiest, |(level, _)| level);
+
+        // If the chance of success drops straight past the zone between two levels, the
+        // easiest level that is a challenge is the best there is.
+        let highest = if level_index(highest) < level_index(lowest) {
+            lowest
+        } else {
+            highest
+        };
+
+        Some(ZoneOfProximalDevelopment {
+            module_id: module_id.to_string(),
+            lowest: lowest.clone(),
+            highest: highest.clone(),
+        })
+    }
+
+    /// Work out a learner's zone in a module under some strategy from their learner
+    /// model, using the first lesson at each difficulty level of the module.
+    pub fn for_learner(
+        learner: &Learner,
+        lessons: &LessonCatalogue,
+        strategy: &Strategy,
+        learner_model: &dyn LearnerModel,
+    ) -> Result<Option<ZoneOfProximalDevelopment>, NeuroNudgeError> {
+        let mut probabilities = vec![];
+        for difficulty_level in DIFFICULTY_LEVELS {
+            let lesson: Option<&Lesson> = lessons
+                .get_lessons()
+                .iter()
+                .find(|lesson| lesson.get_difficulty_level() == &difficulty_level);
+            if let Some(lesson) = lesson {
+                let probability = learner_model.success_probability(learner, lesson, strategy)?;
+                probabilities.push((difficulty_level, probability));
+            }
+        }
+
+        let module_id = lessons
+            .get_lessons()
+            .first()
+            .map(|lesson| lesson.get_module_id().clone())
+            .unwrap_or_default();
+        Ok(ZoneOfProximalDevelopment::from_success_probabilities(
+            &module_id,
+            &probabilities,
+        ))
+    }
+
+    /// Score a recommended difficulty level against the zone.
+    pub fn classify(&self, difficulty_level: &DifficultyLevel) -> RecommendationFit {
+        let index = level_index(difficulty_level);
+        if index < level_index(&self.lowest) {
+            RecommendationFit::TooEasy
+        } else if index > level_index(&self.highest) {
+            RecommendationFit::TooHard
+        } else {
+            RecommendationFit::OnTarget
+        }
+    }
+
+    /// How many levels a recommended difficulty level is outside of the zone, or 0 if
+    /// it is on target.
+    pub fn regret(&self, difficulty_level: &DifficultyLevel) -> u32 {
+        let index = level_index(difficulty_level);
+        let lowest = level_index(&self.lowest);
+        let highest = level_index(&self.highest);
+        if index < lowest {
+            (lowest - index) as u32
+        } else {
+            index.saturating_sub(highest) as u32
+        }
+    }
+}
+
+impl RecommendationCurve {
+    pub fn new() -> RecommendationCurve {
+        RecommendationCurve::default()
+    }
+
+    /// Record the (fit, regret) of every learner's recommendation in an iteration.
+    pub fn record_iteration(&mut self, iteration: u32, scores: &[(RecommendationFit, u32)]) {
+        let count = |fit: RecommendationFit| scores.iter().filter(|(f, _)| *f == fit).count();
+        let on_target = count(RecommendationFit::OnTarget) as u32;
+
+        self.total_recommendations += scores.len() as u32;
+        self.total_on_target += on_target;
+        self.total_regret += scores.iter().map(|(_, regret)| regret).sum::<u32>();
+
+        self.points.push(RecommendationPoint {
+            iteration,
+            too_easy: count(RecommendationFit::TooEasy) as u32,
+            on_target,
+            too_hard: count(RecommendationFit::TooHard) as u32,
+            accuracy: on_target as f64 / scores.len().max(1) as f64,
+            cumulative_accuracy: self.accuracy(),
+            cumulative_regret: self.total_regret as f64 / scores.len().max(1) as f64,
+        });
+    }
+
+    /// The share of all recommendations that were on target.
+    pub fn accuracy(&self) -> f64 {
+        self.total_on_target as f64 / self.total_recommendations.max(1) as f64
+    }
+
+    /// The total regret of all recommendations, averaged over the learners.
+    pub fn regret(&self) -> f64 {
+        self.points
+            .last()
+            .map_or(0.0, |point| point.cumulative_regret)
+    }
+}
+
+/// Format the recommendation curves of some strategies as CSV, with one row per
+/// strategy per iteration.
+pub fn curves_to_csv(curves: &[(u8, RecommendationCurve)]) -> String {
+    let mut csv =
+        "strategy,iteration,too_easy,on_target,too_hard,accuracy,cumulative_accuracy,cumulative_regret\n"
+            .to_string();
+    for (strategy, curve) in curves {
+        for point in &curve.points {
+            csv += &format!(
+                "{},{},{},{},{},{},{},{}\n",
+                strategy,
+                point.iteration,
+                point.too_easy,
+                point.on_target,
+                point.too_hard,
+                point.accuracy,
+                point.cumulative_accuracy,
+                point.cumulative_regret
+            );
+        }
+    }
+    csv
+}
+
+/// Write the recommendation curves of some strategies to a `.csv` or `.json` file.
+pub fn write_curves(
+    path: &Path,
+    curves: &[(u8, RecommendationCurve)],
+) -> Result<(), NeuroNudgeError> {
+    let report = match path.extension().and_then(|e| e.to_str()) {
+        Some("csv") => curves_to_csv(curves),
+        Some("json") => serde_json::to_string_pretty(curves)?,
+        _ => {
+            return Err(NeuroNudgeError::Serialization(format!(
+                "{} is neither a .csv nor a .json file",
+                path.display()
+            )))
+        }
+    };
+
+    let mut curves_file = File::create(path)?;
+    write!(curves_file, "{}", report)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::config::EngineConfig;
+
+    use crate::learner_model::IrtLearnerModel;
+    use crate::simulated_content_shapes::generate_shapes_lessons;
+    use crate::simulated_learners::generate_simulated_learners_with_q_tables;
+
+    #[test]
+    fn zone_moves_up_as_ability_grows() {
+        let lessons = generate_shapes_lessons();
+        let (learner_ids, learners) = generate_simulated_learners_with_q_tables(
+            &lessons,
+            Strategy::BaseQLearning,
+            0.1,
+            &EngineConfig::default(),
+        )
+        .unwrap();
+        let (learner, q_table) = &learners[learner_ids[0]];
+        let lessons = LessonCatalogue::new(lessons);
+
+        let mut learner_model = IrtLearnerModel::default();
+        let zone_at = |learner_model: &IrtLearnerModel| {
+            ZoneOfProximalDevelopment::for_learner(
+                learner,
+                &lessons,
+                q_table.get_strategy(),
+                learner_model,
+            )
+            .unwrap()
+            .unwrap()
+        };
+
+        learner_model.set_ability(learner.get_id(), -1.0);
+        let beginner_zone = zone_at(&learner_model);
+        learner_model.set_ability(learner.get_id(), 2.5);
+        let practised_zone = zone_at(&learner_model);
+
+        assert!(level_index(&practised_zone.lowest) > level_index(&beginner_zone.lowest));
+        assert!(level_index(&practised_zone.highest) > level_index(&beginner_zone.highest));
+    }
+}
@@ -0,0 +1,660 @@
+//! Models of how a simulated learner performs in a lesson.
+//!
+//! The simulation asks a `LearnerModel` for the result of every lesson that a learner
+//! attempts, so the behaviour of the simulated learners can be swapped out (for an item
+//! response theory model, a fatigue-aware model, a model fit to real data and so on)
+//! without touching the simulation loop.
+//!
+
+use std::cmp::max;
+use std::collections::HashMap;
+
+use rand::{Rng, RngCore};
+use types::content::{
+    DifficultyLevel, InstructorEvaluation, InstructorVerdict, Lesson, LessonResult, Question,
+    QuestionAttempt, DIFFICULTY_LEVELS,
+};
+use types::engine::{QTableAlgorithm, Strategy};
+use types::error::NeuroNudgeError;
+use types::learner::{ASDTraitComparison, ASDTraits, CommunicationLevel, Learner};
+
+/// How much each hint closes the gap between a learner's chance of answering a question
+/// correctly and certainty.
+const HINT_HELP: f64 = 0.5;
+/// The evaluator id of the instructor who judges simulated responses to imitation
+/// questions.
+const SIMULATED_INSTRUCTOR_ID: &str = "Simulated Instructor";
+
+/// LearnerModel
+/// Produces the result of a learner attempting a lesson.
+pub trait LearnerModel {
+    /// Simulate `learner` attempting `lesson`. The learner's ASD traits come from the
+    /// learner, and `history` is their q table for the lesson's module, which holds
+    /// their progress and attempts so far. All randomness must be drawn from `rng`, so
+    /// that seeded simulations are reproducible.
+    fn attempt_lesson(
+        &mut self,
+        learner: &Learner,
+        lesson: &Lesson,
+        history: &QTableAlgorithm,
+        rng: &mut dyn RngCore,
+    ) -> Result<LessonResult, NeuroNudgeError>;
+
+    /// The learner's true ability, for models that have one. This is hidden from the
+    /// engine, but lets a simulation check how well the q values track it.
+    fn ability(&self, _learner_id: &str) -> Option<f64> {
+        None
+    }
+
+    /// The learner's true chance of answering a question in `lesson` correctly on their
+    /// first try, from their latent ability alone under the given strategy. This is the
+    /// ground truth that the engine's recommendations are judged against (see
+    /// `ground_truth`), so it must not depend on the engine's q table.
+    fn success_probability(
+        &self,
+        learner: &Learner,
+        lesson: &Lesson,
+        strategy: &Strategy,
+    ) -> Result<f64, NeuroNudgeError>;
+}
+
+/// LearnerModelKind
+/// The learner models that can be chosen from the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum LearnerModelKind {
+    #[default]
+    Default,
+    Irt,
+}
+
+impl LearnerModelKind {
+    /// Create a new model of this kind, with no learner history.
+    pub fn build(&self) -> Box<dyn LearnerModel> {
+        match self {
+            LearnerModelKind::Default => Box::new(DefaultLearnerModel),
+            LearnerModelKind::Irt => Box::new(IrtLearnerModel::default()),
+        }
+    }
+}
+
+/// How readily a learner asks for a hint when they're unsure - learners who communicate
+/// more easily ask more often.
+fn hint_propensity(asd_traits: &ASDTraits) -> f64 {
+    match asd_traits.get_communication_level() {
+        CommunicationLevel::High => 1.0,
+        CommunicationLevel::Medium => 0.75,
+        CommunicationLevel::Low => 0.5,
+    }
+}
+
+/// Whether a learner asks for a hint before their next attempt at a question. They ask
+/// more the less likely they are to answer correctly, as long as the question has hints
+/// that they haven't been given yet.
+fn requests_hint(
+    asd_traits: &ASDTraits,
+    question: &Question,
+    probability: f64,
+    hints_requested: usize,
+    rng: &mut dyn RngCore,
+) -> bool {
+    let hints_available = question.get_hints().as_ref().map_or(0, |hints| hints.len());
+    hints_requested < hints_available
+        && rng.gen::<f64>() < (1.0 - probability).max(0.0) * hint_propensity(asd_traits)
+}
+
+/// The instructor's evaluation of a simulated response, if the question is one that an
+/// instructor judges.
+fn simulated_instructor_evaluation(
+    question: &Question,
+    is_correct: bool,
+) -> Option<InstructorEvaluation> {
+    if !question.is_instructor_scored() {
+        return None;
+    }
+
+    let verdict = if is_correct {
+        InstructorVerdict::Correct
+    } else {
+        InstructorVerdict::Incorrect
+    };
+    Some(InstructorEvaluation::new(
+        verdict,
+        None,
+        SIMULATED_INSTRUCTOR_ID.to_string(),
+    ))
+}
+
+/// The chance of answering a question correctly after some hints.
+fn hinted_probability(probability: f64, hints_requested: usize) -> f64 {
+    1.0 - (1.0 - probability) * (1.0 - HINT_HELP).powi(hints_requested as i32)
+}
+
+/// DefaultLearnerModel
+/// The model the simulation was built with. Each difficulty level has a fixed time range
+/// and chance of answering correctly, and each question is answered at its own level
+/// until the learner gets it right. Under the trait sensitivity strategy, lessons that
+/// run over the learner's attention span take longer, and the chance of answering
+/// correctly depends on how well the learner's traits align with each question's.
+/// Practice makes a lesson a little easier, in proportion to its q value. Before each
+/// attempt, a learner who is unsure may ask for one of the question's hints, which
+/// makes answering correctly more likely.
+#[derive(Debug, Clone, Default)]
+pub struct DefaultLearnerModel;
+
+impl DefaultLearnerModel {
+    /// The chance of answering a question in the lesson correctly, given the learner's
+    /// history so far.
+    fn correctness_factor(
+        &self,
+        learner: &Learner,
+        lesson: &Lesson,
+        question: &Question,
+        history: &QTableAlgorithm,
+    ) -> Result<f32, NeuroNudgeError> {
+        // Only the trait sensitivity strategy eases trait misalignment with practice.
+        let consecutive_attempts = if history.get_strategy() == &Strategy::TraitSensitivity {
+            *history.get_consecutive_attempts_for_difficulty(lesson.get_difficulty_level())?
+        } else {
+            0.0
+        };
+        let current_q_value = *history
+            .get(&(
+                lesson.get_id().clone(),
+                lesson.get_difficulty_level().clone(),
+            ))
+            .unwrap_or(&0.0);
+
+        Ok(practised_correctness_factor(
+            learner,
+            lesson,
+            question,
+            history.get_strategy(),
+            consecutive_attempts,
+            current_q_value,
+        ))
+    }
+}
+
+/// The chance of answering a question in the lesson correctly, after some consecutive
+/// attempts at the lesson's level and with some q value for the lesson. Without any
+/// practice, this is the learner's latent chance of answering correctly.
+fn practised_correctness_factor(
+    learner: &Learner,
+    lesson: &Lesson,
+    question: &Question,
+    strategy: &Strategy,
+    consecutive_attempts: f32,
+    current_q_value: f32,
+) -> f32 {
+    let learner_asd_traits = learner.get_asd_traits();
+
+    // Calculate the probability of answering correctly based on the question's difficulty.
+    let mut correctness_factor: f32 = match lesson.get_question_difficulty_level(question) {
+        DifficultyLevel::VeryEasy => 0.95, // Easier lessons have a higher chance of correctness.
+        DifficultyLevel::Easy => 0.85,
+        DifficultyLevel::Medium => 0.7,
+        DifficultyLevel::Hard => 0.6,
+        DifficultyLevel::VeryHard => 0.55,
+        DifficultyLevel::Expert => 0.5,
+        DifficultyLevel::Master => 0.45,
+        DifficultyLevel::Grandmaster => 0.4,
+    };
+    // ASD trait parameters - if the learner's ASD trait qualities are comparably lower
+    // than the question's ASD trait parameters, the probability of success should decrease
+    // accordingly, based on how much lower/different the learner's traits are.
+    // This is the final strategy, strategy 4. A question without ASD trait parameters
+    // has no trait requirements, so isn't any harder for any learner.
+    if let (Strategy::TraitSensitivity, Some(question_asd_traits)) =
+        (strategy, question.get_asd_traits_parameters())
+    {
+        let alignment_score = learner_asd_traits.calculate_alignment(question_asd_traits);
+
+        // Although the alignment of traits should affect the probability of success,
+        // it should not be the only factor. The learner should still have a chance of
+        // success even if their traits are not aligned with the question's traits - especially
+        // if they have consecutively made a large number of attempts.
+        // Therefore, the alignment score is multiplied by a factor that is inversely proportional
+        // to the number of consecutive attempts.
+
+        // Using 0 as min and 4000 as max due to 5000 iterations being run and unlikely we exceed 4000
+        let normalised_consecutive_attempts = consecutive_attempts / 5000.0;
+
+        correctness_factor *= alignment_score + (normalised_consecutive_attempts * 20.0).min(1.0);
+    }
+
+    // Within the context of what we are solving, as a learner becomes more accustomed
+    // to a particular difficulty or makes progress, their chances of success should increase.
+    // While this doesn't mean mastery, it means it should at least increase, meaning the
+    // correctness_factor variable above in turn should increase, **depending on if the learner
+    // has made progress in that difficulty level**. We should still not make it too easy as
+    // reinforcement is very important for ASD learners even on something they have learnt well
+    // already, but we should make it easier than it was before.
+
+    // If the learner has made progress in the current difficulty level, decrease the difficulty factor
+    // by a factor that is relative to the progress.
+    if current_q_value > 0.0 {
+        correctness_factor += current_q_value * 0.1;
+    }
+
+    // Ultimately, if there is a very low chance, we still don't want the
+    // correctness_factor to go any lower than 5%
+    correctness_factor.max(0.05)
+}
+
+impl LearnerModel for DefaultLearnerModel {
+    fn attempt_lesson(
+        &mut self,
+        learner: &Learner,
+        current_lesson: &Lesson,
+        history: &QTableAlgorithm,
+        rng: &mut dyn RngCore,
+    ) -> Result<LessonResult, NeuroNudgeError> {
+        // Generate a simulated lesson result.
+        let mut question_attempts = Vec::new();
+        let total_questions = current_lesson.get_questions().len();
+
+        // Time taken to complete the lesson //
+        let learner_asd_traits = learner.get_asd_traits();
+        let learner_attention_span = learner_asd_traits.get_attention_span();
+
+        // Calculate the time taken based on lesson difficulty (in seconds).
+        // However, it should also be influenced by the learner's attention span.
+        // For example, if the learner has a low attention span, they will take longer to complete
+        // the lesson.
+        let generated_time_taken_by_difficulty = match current_lesson.get_difficulty_level() {
+            DifficultyLevel::VeryEasy => {
+                // Simulate quicker time for very easy lessons.
+                (rng.gen::<f64>() * 5.0) + 5.0 // Random time between 5 to 10 seconds.
+            }
+            DifficultyLevel::Easy => {
+                (rng.gen::<f64>() * 5.0) + 10.0 // Random time between 10 to 15 seconds.
+            }
+            DifficultyLevel::Medium => {
+                (rng.gen::<f64>() * 10.0) + 20.0 // Random time between 20 to 30 seconds.
+            }
+            DifficultyLevel::Hard => {
+                (rng.gen::<f64>() * 10.0) + 30.0 // Random time between 30 to 40 seconds.
+            }
+            DifficultyLevel::VeryHard => {
+                (rng.gen::<f64>() * 10.0) + 40.0 // Random time between 40 to 50 seconds.
+            }
+            DifficultyLevel::Expert => {
+                (rng.gen::<f64>() * 10.0) + 50.0 // Random time between 50 to 60 seconds.
+            }
+            DifficultyLevel::Master => {
+                (rng.gen::<f64>() * 10.0) + 60.0 // Random time between 60 to 70 seconds.
+            }
+            DifficultyLevel::Grandmaster => {
+                (rng.gen::<f64>() * 10.0) + 70.0 // Random time between 70 to 80 seconds.
+            }
+        } as i32;
+
+        let mut total_time_taken = generated_time_taken_by_difficulty as f64;
+
+        if history.get_strategy() == &Strategy::TraitSensitivity {
+            // Attention span is given in minutes, so convert it to seconds for comparison
+            let attention_span_seconds = learner_attention_span * 60;
+
+            // Calculate a factor representing the extent to which the generated time exceeds the attention span
+            // This factor exponentially increases the time taken based on how much the generated time exceeds the attention span
+            let time_excess_factor = if generated_time_taken_by_difficulty > attention_span_seconds
+            {
+                let excess_time = generated_time_taken_by_difficulty - attention_span_seconds;
+                // The exponential factor could be adjusted as needed for realism
+                let exponential_factor = 1.2;
+                // Apply the exponential increase
+                excess_time as f64 * exponential_factor
+            } else {
+                0.0 // No increase if within attention span
+            };
+
+            // Total time taken is the sum of generated time and the additional time due to attention span
+            total_time_taken = generated_time_taken_by_difficulty as f64 + time_excess_factor;
+
+            // Ensure total time taken is at least the generated time
+            total_time_taken = total_time_taken.max(generated_time_taken_by_difficulty as f64);
+        }
+
+        for question in current_lesson.get_questions() {
+            // Each question is attempted independently, until it is answered correctly.
+            let correctness_factor =
+                self.correctness_factor(learner, current_lesson, question, history)?;
+            let mut attempts = 0;
+            let mut hints_requested = 0;
+            let mut is_correct = false;
+
+            while !is_correct {
+                // The learner may ask for a hint first if they're unsure, which makes
+                // answering correctly more likely.
+                if requests_hint(
+                    learner_asd_traits,
+                    question,
+                    correctness_factor as f64,
+                    hints_requested,
+                    rng,
+                ) {
+                    hints_requested += 1;
+                }
+
+                let rand_value = rng.gen::<f64>();
+                // Simulate learner's answer attempt (random correctness).
+                is_correct =
+                    rand_value < hinted_probability(correctness_factor as f64, hints_requested);
+
+                // Increment the number of attempts.
+                attempts += 1;
+            }
+
+            // Create a QuestionAttempt object.
+            let mut question_attempt = QuestionAttempt::new(
+                question.get_id().to_string(),
+                (total_time_taken / total_questions as f64) as i32, // Time taken for each question on average.
+                attempts, // Total attempts it took to get it right.
+                max(0, attempts - 1),
+            );
+            for _ in 0..hints_requested {
+                question_attempt.increment_hints_requested();
+            }
+            if let Some(evaluation) = simulated_instructor_evaluation(question, is_correct) {
+                question_attempt = question_attempt.with_instructor_evaluation(evaluation);
+            }
+
+            question_attempts.push(question_attempt);
+        }
+
+        // Create a LessonResult.
+        Ok(LessonResult::new(
+            current_lesson.get_difficulty_level().clone(),
+            total_time_taken as i32, // Use the actual score or progress.
+            total_questions as i32,  // Number of questions attempted.
+            question_attempts,
+        ))
+    }
+
+    fn success_probability(
+        &self,
+        learner: &Learner,
+        lesson: &Lesson,
+        strategy: &Strategy,
+    ) -> Result<f64, NeuroNudgeError> {
+        // The chance of answering a question in the lesson without any practice, averaged
+        // over its questions.
+        let mut total_probability = 0.0;
+        for question in lesson.get_questions() {
+            total_probability +=
+                (practised_correctness_factor(learner, lesson, question, strategy, 0.0, 0.0)
+                    as f64)
+                    .min(1.0);
+        }
+        Ok(total_probability / lesson.get_questions().len().max(1) as f64)
+    }
+}
+
+/// IrtParameters
+/// The parameters of the item response theory learner model.
+#[derive(Debug, Clone)]
+pub struct IrtParameters {
+    /// The ability every learner starts with, and fades back towards.
+    pub initial_ability: f64,
+    /// How much a correct answer improves ability, scaled by how unlikely it was.
+    pub learning_rate: f64,
+    /// The share of the ability gained above the initial ability that fades after each
+    /// lesson.
+    pub forgetting_rate: f64,
+    /// The difficulty of questions at the easiest and hardest levels. The levels in
+    /// between are spread evenly.
+    pub difficulty_range: (f64, f64),
+    /// How far a question's difficulty can be from its level's difficulty.
+    pub difficulty_jitter: f64,
+    /// The range that each question's discrimination is drawn from.
+    pub discrimination_range: (f64, f64),
+    /// The most attempts a learner makes at a question before giving up on it.
+    pub max_attempts: i32,
+}
+
+impl Default for IrtParameters {
+    fn default() -> Self {
+        IrtParameters {
+            initial_ability: -1.0,
+            learning_rate: 0.05,
+            forgetting_rate: 0.002,
+            difficulty_range: (-2.0, 3.0),
+            difficulty_jitter: 0.3,
+            discrimination_range: (0.8, 2.0),
+            max_attempts: 10,
+        }
+    }
+}
+
+/// IrtItem
+/// The item response theory parameters of a question.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrtItem {
+    pub difficulty: f64,
+    pub discrimination: f64,
+}
+
+impl IrtItem {
+    /// The chance that a learner with some ability answers the question correctly, as
+    /// per the two parameter logistic model.
+    pub fn probability_correct(&self, ability: f64) -> f64 {
+        1.0 / (1.0 + (-self.discrimination * (ability - self.difficulty)).exp())
+    }
+}
+
+/// IrtLearnerModel
+/// A learner model based on item response theory. Each learner has a hidden ability,
+/// and each question a difficulty and discrimination, which together give the chance
+/// of answering it correctly. Every correct answer improves the learner's ability,
+/// more so for questions they were unlikely to get right, while after every lesson
+/// some of the ability gained fades. So ability only keeps growing with successful,
+/// challenging practice. Learners ask for hints in the same way as in the
+/// `DefaultLearnerModel`, and learn less from answers that a hint made easy.
+///
+/// Questions that haven't been given parameters with `set_item` get them the first
+/// time they are attempted, with a difficulty based on their lesson's level.
+#[derive(Debug, Clone, Default)]
+pub struct IrtLearnerModel {
+    parameters: IrtParameters,
+    /// The ability of each learner, by learner id.
+    abilities: HashMap<String, f64>,
+    /// The parameters of each question, by question id.
+    items: HashMap<String, IrtItem>,
+}
+
+impl IrtLearnerModel {
+    pub fn new(parameters: IrtParameters) -> IrtLearnerModel {
+        IrtLearnerModel {
+            parameters,
+            abilities: HashMap::new(),
+            items: HashMap::new(),
+        }
+    }
+
+    /// Set the ability of a learner, e.g. to start them ahead of the others.
+    pub fn set_ability(&mut self, learner_id: &str, ability: f64) {
+        self.abilities.insert(learner_id.to_string(), ability);
+    }
+
+    /// Set the parameters of a question.
+    pub fn set_item(&mut self, question_id: &str, item: IrtItem) {
+        self.items.insert(question_id.to_string(), item);
+    }
+
+    pub fn get_item(&self, question_id: &str) -> Option<&IrtItem> {
+        self.items.get(question_id)
+    }
+
+    /// The difficulty of questions at some level, before jitter.
+    fn level_difficulty(&self, difficulty_level: &DifficultyLevel) -> f64 {
+        let (easiest, hardest) = self.parameters.difficulty_range;
+        let index = DIFFICULTY_LEVELS
+            .iter()
+            .position(|d| d == difficulty_level)
+            .unwrap_or(0);
+        easiest + (hardest - easiest) * index as f64 / (DIFFICULTY_LEVELS.len() - 1) as f64
+    }
+
+    /// Get the parameters of a question, drawing new ones if it has none yet.
+    fn get_or_create_item(
+        &mut self,
+        question_id: &str,
+        difficulty_level: &DifficultyLevel,
+        rng: &mut dyn RngCore,
+    ) -> IrtItem {
+        if let Some(item) = self.items.get(question_id) {
+            return item.clone();
+        }
+
+        let jitter = self.parameters.difficulty_jitter;
+        let (min_discrimination, max_discrimination) = self.parameters.discrimination_range;
+        let item = IrtItem {
+            difficulty: self.level_difficulty(difficulty_level)
+                + (rng.gen::<f64>() * 2.0 - 1.0) * jitter,
+            discrimination: min_discrimination
+                + rng.gen::<f64>() * (max_discrimination - min_discrimination),
+        };
+        self.items.insert(question_id.to_string(), item.clone());
+        item
+    }
+}
+
+impl LearnerModel for IrtLearnerModel {
+    fn attempt_lesson(
+        &mut self,
+        learner: &Learner,
+        lesson: &Lesson,
+        history: &QTableAlgorithm,
+        rng: &mut dyn RngCore,
+    ) -> Result<LessonResult, NeuroNudgeError> {
+        let difficulty_level = lesson.get_difficulty_level();
+        let mut ability = *self
+            .abilities
+            .get(learner.get_id())
+            .unwrap_or(&self.parameters.initial_ability);
+
+        let items: Vec<IrtItem> = lesson
+            .get_questions()
+            .iter()
+            .map(|question| {
+                self.get_or_create_item(
+                    question.get_id(),
+                    lesson.get_question_difficulty_level(question),
+                    rng,
+                )
+            })
+            .collect();
+
+        // The learner works through the lesson within the expected time range for its
+        // level, but takes up to twice as long the less likely they are to succeed.
+        let total_questions = items.len();
+        let mean_probability = items
+            .iter()
+            .map(|item| item.probability_correct(ability))
+            .sum::<f64>()
+            / total_questions.max(1) as f64;
+        let (min_time, max_time) = *history
+            .get_config()
+            .expected_time_ranges
+            .get(difficulty_level);
+        let time_taken = (min_time as f64 + rng.gen::<f64>() * (max_time - min_time) as f64)
+            * (2.0 - mean_probability);
+
+        let mut question_attempts = vec![];
+        for (question, item) in lesson.get_questions().iter().zip(&items) {
+            let mut attempts = 0;
+            let mut hints_requested = 0;
+            let mut is_correct = false;
+            while !is_correct && attempts < self.parameters.max_attempts {
+                if requests_hint(
+                    learner.get_asd_traits(),
+                    question,
+                    item.probability_correct(ability),
+                    hints_requested,
+                    rng,
+                ) {
+                    hints_requested += 1;
+                }
+
+                let probability =
+                    hinted_probability(item.probability_correct(ability), hints_requested);
+                is_correct = rng.gen::<f64>() < probability;
+                attempts += 1;
+
+                // Getting an unlikely question right teaches the learner the most, so a
+                // correct answer after a hint teaches them less than one without.
+                if is_correct {
+                    ability += self.parameters.learning_rate * (1.0 - probability);
+                }
+            }
+
+            let incorrect_attempts = if is_correct { attempts - 1 } else { attempts };
+            let mut question_attempt = QuestionAttempt::new(
+                question.get_id().to_string(),
+                (time_taken / total_questions as f64) as i32,
+                attempts,
+                incorrect_attempts,
+            );
+            for _ in 0..hints_requested {
+                question_attempt.increment_hints_requested();
+            }
+            if let Some(evaluation) = simulated_instructor_evaluation(question, is_correct) {
+                question_attempt = question_attempt.with_instructor_evaluation(evaluation);
+            }
+            question_attempts.push(question_attempt);
+        }
+
+        // Some of the ability gained fades after every lesson.
+        let initial_ability = self.parameters.initial_ability;
+        ability -= (ability - initial_ability) * self.parameters.forgetting_rate;
+        self.abilities.insert(learner.get_id().clone(), ability);
+
+        Ok(LessonResult::new(
+            difficulty_level.clone(),
+            time_taken as i32,
+            total_questions as i32,
+            question_attempts,
+        ))
+    }
+
+    fn ability(&self, learner_id: &str) -> Option<f64> {
+        Some(
+            *self
+                .abilities
+                .get(learner_id)
+                .unwrap_or(&self.parameters.initial_ability),
+        )
+    }
+
+    fn success_probability(
+        &self,
+        learner: &Learner,
+        lesson: &Lesson,
+        _strategy: &Strategy,
+    ) -> Result<f64, NeuroNudgeError> {
+        // Use the parameters of the lesson's questions where they've been drawn, or those
+        // of a typical question at their level otherwise.
+        let (min_discrimination, max_discrimination) = self.parameters.discrimination_range;
+        let typical_item = |difficulty_level: &DifficultyLevel| IrtItem {
+            difficulty: self.level_difficulty(difficulty_level),
+            discrimination: (min_discrimination + max_discrimination) / 2.0,
+        };
+        let ability = self
+            .ability(learner.get_id())
+            .unwrap_or(self.parameters.initial_ability);
+        if lesson.get_questions().is_empty() {
+            return Ok(typical_item(lesson.get_difficulty_level()).probability_correct(ability));
+        }
+
+        let total_probability: f64 = lesson
+            .get_questions()
+            .iter()
+            .map(|question| match self.items.get(question.get_id()) {
+                Some(item) => item.probability_correct(ability),
+                None => typical_item(lesson.get_question_difficulty_level(question))
+                    .probability_correct(ability),
+            })
+            .sum();
+        Ok(total_probability / lesson.get_questions().len() as f64)
+    }
+}
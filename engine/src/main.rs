@@ -13,29 +13,292 @@
 //! (using GPT). It does NOT provide an application experience for the
 //! learning.
 //!
+//! Run with no arguments for an interactive menu, or use a subcommand (see `--help`)
+//! to run simulations from a script, e.g.
+//! `engine simulate --strategy 3 --iterations 5000 --seed 42 --out results.json`, or
+//! `engine experiment --iterations 1000,5000 --epsilons 0.1,0.3 --out report.csv`.
+//! `engine compare --learner-model irt` simulates learners with a hidden true ability,
+//! and reports how closely each strategy's q values track it.
+//! `engine lint-content shapes.toml` checks a content file for errors and warnings.
+//!
 //! Note that the state and types for stuff like learner, lesson etc are
 //! defined in the `types` module.
 //!
 
-use std::fs::File;
-use std::io::Write;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use types::config::EngineConfig;
+use types::content::ContentModule;
+use types::content_file;
+use types::error::NeuroNudgeError;
+
+use crate::experiment::{ExperimentGrid, ReportFormat, Statistics};
+use crate::learner_model::LearnerModelKind;
+use crate::metrics::StrategyComparison;
+use crate::simulate::SimulationOptions;
 
+pub mod experiment;
+pub mod ground_truth;
+pub mod learner_model;
+pub mod metrics;
 pub mod simulate;
 pub mod simulated_content_actions;
 pub mod simulated_content_shapes;
 pub mod simulated_learners;
 
+/// The grid that "Run All" in the interactive menu runs.
+const RUN_ALL_ITERATIONS: [u32; 4] = [1000, 5000, 10000, 20000];
+const RUN_ALL_REPETITIONS: u64 = 5;
+const RUN_ALL_EPSILON: f32 = 0.3;
+const EXPERIMENT_REPORT_FILE: &str = "experiment_report.csv";
+
+const STRATEGY_DESCRIPTIONS: [&str; 4] = [
+    "Simulate Q Learning without Mastery Thresholds",
+    "Simulate Q Learning with Mastery Thresholds",
+    "Simulate Q Learning with Mastery Thresholds and Decaying Q Values",
+    "Simulate Q Learning with Mastery Thresholds, Decaying Q Values and ASD Trait Sensitivity",
+];
+
+#[derive(Parser)]
+#[command(
+    name = "engine",
+    about = "Simulate the NeuroNudge recommendation engine. Runs an interactive menu when no subcommand is given."
+)]
+struct Cli {
+    /// Engine config to use, as a .toml or .json file. The default config is used if
+    /// none is given.
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    /// Seed for the rng, so that the same command gives byte-identical results.
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+    /// How the simulated learners answer questions: the default model, or an item
+    /// response theory model with a hidden ability that the q values can be checked
+    /// against.
+    #[arg(long, global = true, value_enum, default_value_t = LearnerModelKind::Default)]
+    learner_model: LearnerModelKind,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the simulation for one strategy and write its results to a JSON file.
+    Simulate {
+        /// The strategy to simulate, 1 to 4.
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=4))]
+        strategy: u8,
+        #[arg(long, default_value_t = 5000)]
+        iterations: u32,
+        #[arg(long, default_value_t = 0.3)]
+        epsilon: f32,
+        /// Where to write the results. Defaults to
+        /// strategy_<strategy>_simulation_results_i<iterations>.json
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Simulate some strategies and compare their learning outcomes in a table.
+    Compare {
+        /// Comma separated strategies to compare.
+        #[arg(
+            long,
+            value_delimiter = ',',
+            value_parser = clap::value_parser!(u8).range(1..=4),
+            default_values_t = [1, 2, 3, 4]
+        )]
+        strategies: Vec<u8>,
+        #[arg(long, default_value_t = 5000)]
+        iterations: u32,
+        #[arg(long, default_value_t = 0.3)]
+        epsilon: f32,
+        /// Also write the comparison to a .csv file, or a .json file that includes the
+        /// metrics of every learner.
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Also write each strategy's recommendation accuracy and regret after every
+        /// iteration, judged against the learners' true zones of proximal development,
+        /// to a .csv or .json file.
+        #[arg(long)]
+        curves: Option<PathBuf>,
+    },
+    /// Time the simulation of some strategies, repeating each a number of times.
+    Benchmark {
+        /// Comma separated strategies to benchmark.
+        #[arg(
+            long,
+            value_delimiter = ',',
+            value_parser = clap::value_parser!(u8).range(1..=4),
+            default_values_t = [1, 2, 3, 4]
+        )]
+        strategies: Vec<u8>,
+        #[arg(long, default_value_t = 5000)]
+        iterations: u32,
+        #[arg(long, default_value_t = 5)]
+        repetitions: u32,
+    },
+    /// Run the simulation over a grid of strategies, iteration counts and epsilons,
+    /// once per seed, and write summary statistics of each cell to a CSV or JSON report.
+    #[command(alias = "run-all")]
+    Experiment {
+        /// Comma separated strategies.
+        #[arg(
+            long,
+            value_delimiter = ',',
+            value_parser = clap::value_parser!(u8).range(1..=4),
+            default_values_t = [1, 2, 3, 4]
+        )]
+        strategies: Vec<u8>,
+        /// Comma separated iteration counts.
+        #[arg(long, value_delimiter = ',', default_values_t = RUN_ALL_ITERATIONS)]
+        iterations: Vec<u32>,
+        /// Comma separated epsilons.
+        #[arg(long, value_delimiter = ',', default_values_t = [RUN_ALL_EPSILON])]
+        epsilons: Vec<f32>,
+        /// Comma separated seeds, each giving one run of every cell. Defaults to
+        /// `--repetitions` seeds counting up from `--seed` (or 0).
+        #[arg(long, value_delimiter = ',')]
+        seeds: Option<Vec<u64>>,
+        #[arg(long, default_value_t = RUN_ALL_REPETITIONS)]
+        repetitions: u64,
+        /// The report to write, a .csv or .json file.
+        #[arg(long, default_value = EXPERIMENT_REPORT_FILE)]
+        out: PathBuf,
+    },
+    /// Check the modules of some content files for errors and warnings, exiting with
+    /// an error if any are invalid. Checks the simulated Shapes and Actions modules if
+    /// no files are given.
+    LintContent {
+        /// The .toml, .json or .yaml content files to check.
+        files: Vec<PathBuf>,
+    },
+}
+
 fn main() {
+    let cli = Cli::parse();
+
+    let config = match &cli.config {
+        Some(path) => match EngineConfig::load(path) {
+            Ok(config) => config,
+            Err(error) => {
+                eprintln!(">> Could not load the engine config: {}", error);
+                std::process::exit(1);
+            }
+        },
+        None => EngineConfig::default(),
+    };
+
+    let command = match cli.command {
+        Some(command) => command,
+        None => {
+            run_interactive(&config, cli.seed, cli.learner_model);
+            return;
+        }
+    };
+
+    let result = match command {
+        Command::Simulate {
+            strategy,
+            iterations,
+            epsilon,
+            out,
+        } => {
+            let options = SimulationOptions {
+                iterations,
+                epsilon,
+                config,
+                output_path: Some(
+                    out.unwrap_or_else(|| simulate::default_output_path(strategy, iterations)),
+                ),
+            };
+            simulate::run_simulation_strategy(
+                strategy,
+                &options,
+                cli.learner_model.build().as_mut(),
+                &mut new_rng(cli.seed),
+            )
+            .map(|_| ())
+        }
+        Command::Compare {
+            strategies,
+            iterations,
+            epsilon,
+            out,
+            curves,
+        } => {
+            let options = SimulationOptions {
+                iterations,
+                epsilon,
+                config,
+                output_path: None,
+            };
+            run_comparison(
+                &strategies,
+                &options,
+                cli.learner_model,
+                cli.seed,
+                out.as_deref(),
+                curves.as_deref(),
+            )
+        }
+        Command::Benchmark {
+            strategies,
+            iterations,
+            repetitions,
+        } => run_benchmark(
+            &strategies,
+            iterations,
+            repetitions,
+            config,
+            cli.learner_model,
+            &mut new_rng(cli.seed),
+        ),
+        Command::Experiment {
+            strategies,
+            iterations,
+            epsilons,
+            seeds,
+            repetitions,
+            out,
+        } => {
+            let seeds = seeds.unwrap_or_else(|| {
+                let first_seed = cli.seed.unwrap_or(0);
+                (first_seed..first_seed + repetitions).collect()
+            });
+            let grid = ExperimentGrid {
+                strategies,
+                iterations,
+                epsilons,
+                seeds,
+                learner_model: cli.learner_model,
+            };
+            run_experiment(&grid, &config, &out)
+        }
+        Command::LintContent { files } => match run_lint_content(&files) {
+            Ok(false) => std::process::exit(1),
+            result => result.map(|_| ()),
+        },
+    };
+
+    if let Err(error) = result {
+        eprintln!(">> {}", error);
+        std::process::exit(1);
+    }
+}
+
+/// The interactive menu, used when the engine is run without a subcommand.
+fn run_interactive(config: &EngineConfig, seed: Option<u64>, learner_model: LearnerModelKind) {
     println!(">> Welcome to NeuroNudge!");
-    let mut all_time_statistics_file = File::create("all_time_statistics.txt").unwrap();
 
     loop {
         // Ask which strategy you want to simulate
         println!(">> Which strategy do you want to simulate?");
-        println!(">> 1. Simulate Q Learning without Mastery Thresholds");
-        println!(">> 2. Simulate Q Learning with Mastery Thresholds");
-        println!(">> 3. Simulate Q Learning with Mastery Thresholds and Decaying Q Values");
-        println!(">> 4. Simulate Q Learning with Mastery Thresholds, Decaying Q Values and ASD Trait Sensitivity");
+        for (index, description) in STRATEGY_DESCRIPTIONS.iter().enumerate() {
+            println!(">> {}. {}", index + 1, description);
+        }
         println!(">> 5. Run All");
         println!(">> Q: Quit NeuroNudge");
 
@@ -52,292 +315,208 @@ fn main() {
             break;
         }
 
-        if input != "1" && input != "2" && input != "3" && input != "4" && input != "5" {
-            println!(">> Invalid input. Please try again.");
+        let strategy = match input.parse::<u8>() {
+            Ok(strategy) if (1..=5).contains(&strategy) => strategy,
+            _ => {
+                println!(">> Invalid input. Please try again.");
+                continue;
+            }
+        };
+
+        if strategy == 5 {
+            // Every strategy at 1k, 5k, 10k and 20k iterations, 5 times each.
+            let grid = ExperimentGrid {
+                strategies: vec![1, 2, 3, 4],
+                iterations: RUN_ALL_ITERATIONS.to_vec(),
+                epsilons: vec![RUN_ALL_EPSILON],
+                seeds: match seed {
+                    Some(seed) => (seed..seed + RUN_ALL_REPETITIONS).collect(),
+                    None => (0..RUN_ALL_REPETITIONS)
+                        .map(|_| rand::random::<u64>())
+                        .collect(),
+                },
+                learner_model,
+            };
+            if let Err(error) = run_experiment(&grid, config, EXPERIMENT_REPORT_FILE.as_ref()) {
+                println!(">> Run All failed: {}", error);
+            }
             continue;
         }
 
-        let strategy = input.parse::<u8>().unwrap();
-
-        if strategy == 1 {
-            println!(
-                ">> You have selected Strategy 1: Simulate Q Learning without Mastery Thresholds"
-            );
-
-            println!(">> Strategy 1: Running simulation now...");
-            let time = std::time::Instant::now();
-            simulate::run_simulation_strategy_1(None);
-            let elapsed = time.elapsed();
-
-            write!(
-                &mut all_time_statistics_file,
-                "Strategy 1: {}\n",
+        println!(
+            ">> You have selected Strategy {}: {}",
+            strategy,
+            STRATEGY_DESCRIPTIONS[strategy as usize - 1]
+        );
+        println!(">> Strategy {}: Running simulation now...", strategy);
+
+        let mut options = SimulationOptions {
+            config: config.clone(),
+            ..SimulationOptions::default()
+        };
+        options.output_path = Some(simulate::default_output_path(strategy, options.iterations));
+        let time = std::time::Instant::now();
+        let result = simulate::run_simulation_strategy(
+            strategy,
+            &options,
+            learner_model.build().as_mut(),
+            &mut new_rng(seed),
+        );
+        let elapsed = time.elapsed();
+
+        match result {
+            Ok(_) => println!(
+                ">> Strategy {}: Simulation complete in {}ms!",
+                strategy,
                 elapsed.as_millis()
-            )
-            .unwrap();
-
-            println!(">> Strategy 1: Simulation complete!");
-        } else if strategy == 2 {
-            println!(
-                ">> You have selected Strategy 2: Simulate Q Learning with Mastery Thresholds"
-            );
-
-            println!(">> Strategy 2: Running simulation now...");
-
-            let time = std::time::Instant::now();
-            simulate::run_simulation_strategy_2(None);
-            let elapsed = time.elapsed();
-
-            write!(
-                &mut all_time_statistics_file,
-                "Strategy 2: {}\n",
-                elapsed.as_millis()
-            )
-            .unwrap();
+            ),
+            Err(error) => println!(">> Strategy {}: Simulation failed: {}", strategy, error),
+        }
+    }
+}
 
-            println!(">> Strategy 2: Simulation complete!");
-        } else if strategy == 3 {
-            println!(">> You have selected Strategy 3: Simulate Q Learning with Mastery Thresholds and Decaying Q Values");
+/// Create the rng for a simulation run. With a seed, every run chosen from the menu
+/// starts from the same rng state, so the same choice gives byte-identical results.
+fn new_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
 
-            println!(">> Strategy 3: Running simulation now...");
+/// Run each strategy the given number of times, printing statistics of how long a run
+/// took.
+fn run_benchmark(
+    strategies: &[u8],
+    iterations: u32,
+    repetitions: u32,
+    config: EngineConfig,
+    learner_model: LearnerModelKind,
+    rng: &mut StdRng,
+) -> Result<(), NeuroNudgeError> {
+    let options = SimulationOptions {
+        iterations,
+        config,
+        ..SimulationOptions::default()
+    };
+
+    for &strategy in strategies {
+        let mut runtimes = vec![];
+        for _ in 0..repetitions {
             let time = std::time::Instant::now();
-            simulate::run_simulation_strategy_3(None);
-            let elapsed = time.elapsed();
+            simulate::run_simulation_strategy(
+                strategy,
+                &options,
+                learner_model.build().as_mut(),
+                rng,
+            )?;
+            runtimes.push(time.elapsed().as_secs_f64() * 1000.0);
+        }
 
-            write!(
-                &mut all_time_statistics_file,
-                "Strategy 3: {}\n",
-                elapsed.as_millis()
-            )
-            .unwrap();
+        let runtime = Statistics::from_samples(&runtimes);
+        println!(
+            "Strategy {} ({} iterations x {}): mean {:.1}ms, std dev {:.1}ms, min {:.1}ms, p50 {:.1}ms, p95 {:.1}ms, max {:.1}ms",
+            strategy,
+            iterations,
+            repetitions,
+            runtime.mean,
+            runtime.std_dev,
+            runtime.min,
+            runtime.p50,
+            runtime.p95,
+            runtime.max
+        );
+    }
 
-            println!(">> Strategy 3: Simulation complete!");
-        } else if strategy == 4 {
-            println!(">> You have selected Strategy 4: Simulate Q Learning with Mastery Thresholds, Decaying Q Values and ASD Trait Sensitivity");
+    Ok(())
+}
 
-            println!(">> Strategy 4: Running simulation now...");
-            let time = std::time::Instant::now();
-            simulate::run_simulation_strategy_4(None);
-            let elapsed = time.elapsed();
+/// Simulate each strategy and print a table comparing their learning outcomes. Each
+/// strategy's simulation starts from the same seed, if one is given.
+fn run_comparison(
+    strategies: &[u8],
+    options: &SimulationOptions,
+    learner_model: LearnerModelKind,
+    seed: Option<u64>,
+    comparison_path: Option<&std::path::Path>,
+    curves_path: Option<&std::path::Path>,
+) -> Result<(), NeuroNudgeError> {
+    let mut comparison = StrategyComparison::new();
+    let mut curves = vec![];
+    for &strategy in strategies {
+        let summary = simulate::run_simulation_strategy(
+            strategy,
+            options,
+            learner_model.build().as_mut(),
+            &mut new_rng(seed),
+        )?;
+        comparison.add_strategy(strategy, summary.learner_metrics);
+        curves.push((strategy, summary.recommendation_curve));
+    }
 
-            write!(
-                &mut all_time_statistics_file,
-                "Strategy 4 Actions: {}\n",
-                elapsed.as_millis()
-            )
-            .unwrap();
-
-            println!(">> Strategy 4: Simulation complete!");
-        } else if strategy == 5 {
-            // No printing logs needed
-            // 1000 Iterations, 5 times each
-
-            println!("Running 1k iterations...");
-            for _ in 0..5 {
-                // 1
-                let time = std::time::Instant::now();
-                simulate::run_simulation_strategy_1(Some(1000));
-                let elapsed = time.elapsed();
-
-                write!(
-                    &mut all_time_statistics_file,
-                    "Strategy 1: {}\n",
-                    elapsed.as_millis()
-                )
-                .unwrap();
-
-                // 2
-                let time = std::time::Instant::now();
-                simulate::run_simulation_strategy_2(Some(1000));
-                let elapsed = time.elapsed();
-
-                write!(
-                    &mut all_time_statistics_file,
-                    "Strategy 2: {}\n",
-                    elapsed.as_millis()
-                )
-                .unwrap();
-
-                // 3
-                let time = std::time::Instant::now();
-                simulate::run_simulation_strategy_3(Some(1000));
-                let elapsed = time.elapsed();
-
-                write!(
-                    &mut all_time_statistics_file,
-                    "Strategy 3: {}\n",
-                    elapsed.as_millis()
-                )
-                .unwrap();
-
-                // 4
-                let time = std::time::Instant::now();
-                simulate::run_simulation_strategy_4(Some(1000));
-                let elapsed = time.elapsed();
-
-                write!(
-                    &mut all_time_statistics_file,
-                    "Strategy 4: {}\n",
-                    elapsed.as_millis()
-                )
-                .unwrap();
-            }
+    print!("{}", comparison);
+    if let Some(comparison_path) = comparison_path {
+        comparison.write(comparison_path)?;
+        println!(">> Comparison written to {}", comparison_path.display());
+    }
+    if let Some(curves_path) = curves_path {
+        ground_truth::write_curves(curves_path, &curves)?;
+        println!(
+            ">> Recommendation curves written to {}",
+            curves_path.display()
+        );
+    }
+    Ok(())
+}
 
-            println!("Running 5k iterations...");
-            // 5000 Iterations, 5 times each
-            for _ in 0..5 {
-                // 1
-                let time = std::time::Instant::now();
-                simulate::run_simulation_strategy_1(Some(5000));
-                let elapsed = time.elapsed();
-
-                write!(
-                    &mut all_time_statistics_file,
-                    "Strategy 1: {}\n",
-                    elapsed.as_millis()
-                )
-                .unwrap();
-
-                // 2
-                let time = std::time::Instant::now();
-                simulate::run_simulation_strategy_2(Some(5000));
-                let elapsed = time.elapsed();
-
-                write!(
-                    &mut all_time_statistics_file,
-                    "Strategy 2: {}\n",
-                    elapsed.as_millis()
-                )
-                .unwrap();
-
-                // 3
-                let time = std::time::Instant::now();
-                simulate::run_simulation_strategy_3(Some(5000));
-                let elapsed = time.elapsed();
-
-                write!(
-                    &mut all_time_statistics_file,
-                    "Strategy 3: {}\n",
-                    elapsed.as_millis()
-                )
-                .unwrap();
-
-                // 4
-                let time = std::time::Instant::now();
-                simulate::run_simulation_strategy_4(Some(5000));
-                let elapsed = time.elapsed();
-
-                write!(
-                    &mut all_time_statistics_file,
-                    "Strategy 4: {}\n",
-                    elapsed.as_millis()
-                )
-                .unwrap();
-            }
+/// Run an experiment and write its report.
+fn run_experiment(
+    grid: &ExperimentGrid,
+    config: &EngineConfig,
+    report_path: &std::path::Path,
+) -> Result<(), NeuroNudgeError> {
+    let format = ReportFormat::from_path(report_path)?;
+    let cells = experiment::run_experiment(grid, config)?;
+    experiment::write_report(report_path, format, &cells)?;
+    println!(">> Experiment report written to {}", report_path.display());
+    Ok(())
+}
 
-            println!("Running 10k iterations...");
-            // 10000 Iterations, 5 times each
-            for _ in 0..5 {
-                // 1
-                let time = std::time::Instant::now();
-                simulate::run_simulation_strategy_1(Some(10000));
-                let elapsed = time.elapsed();
-
-                write!(
-                    &mut all_time_statistics_file,
-                    "Strategy 1: {}\n",
-                    elapsed.as_millis()
-                )
-                .unwrap();
-
-                // 2
-                let time = std::time::Instant::now();
-                simulate::run_simulation_strategy_2(Some(10000));
-                let elapsed = time.elapsed();
-
-                write!(
-                    &mut all_time_statistics_file,
-                    "Strategy 2: {}\n",
-                    elapsed.as_millis()
-                )
-                .unwrap();
-
-                // 3
-                let time = std::time::Instant::now();
-                simulate::run_simulation_strategy_3(Some(10000));
-                let elapsed = time.elapsed();
-
-                write!(
-                    &mut all_time_statistics_file,
-                    "Strategy 3: {}\n",
-                    elapsed.as_millis()
-                )
-                .unwrap();
-
-                // 4
-                let time = std::time::Instant::now();
-                simulate::run_simulation_strategy_4(Some(10000));
-                let elapsed = time.elapsed();
-
-                write!(
-                    &mut all_time_statistics_file,
-                    "Strategy 4: {}\n",
-                    elapsed.as_millis()
-                )
-                .unwrap();
+/// Validate the modules of some content files, or the simulated modules, printing every
+/// issue found. Returns whether every module is valid.
+fn run_lint_content(files: &[PathBuf]) -> Result<bool, NeuroNudgeError> {
+    let mut modules = vec![];
+    let mut is_valid = true;
+    if files.is_empty() {
+        let mut shapes = ContentModule::new("Shapes".to_string());
+        shapes.with_lessons(simulated_content_shapes::generate_shapes_lessons());
+        let mut actions = ContentModule::new("Actions".to_string());
+        actions.with_lessons(simulated_content_actions::generate_actions_lessons());
+        modules.push(shapes);
+        modules.push(actions);
+    }
+    for path in files {
+        match content_file::load_content_modules(path) {
+            Ok(file_modules) => modules.extend(file_modules),
+            Err(error) => {
+                println!(">> {}: {}", path.display(), error);
+                is_valid = false;
             }
+        }
+    }
 
-            println!("Running 20k iterations...");
-            // 20000 Iterations, 5 times each
-            for _ in 0..5 {
-                // 1
-                let time = std::time::Instant::now();
-                simulate::run_simulation_strategy_1(Some(20000));
-                let elapsed = time.elapsed();
-
-                write!(
-                    &mut all_time_statistics_file,
-                    "Strategy 1: {}\n",
-                    elapsed.as_millis()
-                )
-                .unwrap();
-
-                // 2
-                let time = std::time::Instant::now();
-                simulate::run_simulation_strategy_2(Some(20000));
-                let elapsed = time.elapsed();
-
-                write!(
-                    &mut all_time_statistics_file,
-                    "Strategy 2: {}\n",
-                    elapsed.as_millis()
-                )
-                .unwrap();
-
-                // 3
-                let time = std::time::Instant::now();
-                simulate::run_simulation_strategy_3(Some(20000));
-                let elapsed = time.elapsed();
-
-                write!(
-                    &mut all_time_statistics_file,
-                    "Strategy 3: {}\n",
-                    elapsed.as_millis()
-                )
-                .unwrap();
-
-                // 4
-                let time = std::time::Instant::now();
-                simulate::run_simulation_strategy_4(Some(20000));
-                let elapsed = time.elapsed();
-
-                write!(
-                    &mut all_time_statistics_file,
-                    "Strategy 4: {}\n",
-                    elapsed.as_millis()
-                )
-                .unwrap();
-            }
+    for module in &modules {
+        let validation = module.validate();
+        println!(
+            ">> {}: {} errors, {} warnings",
+            module.get_name(),
+            validation.errors().count(),
+            validation.warnings().count()
+        );
+        for issue in &validation.issues {
+            println!(">>   {}", issue);
         }
+        is_valid &= validation.is_valid();
     }
+    Ok(is_valid)
 }
@@ -0,0 +1,556 @@
+//! Learning outcome metrics of a simulation.
+//!
+//! While a simulation runs, a `MetricsRecorder` is given each learner's q table after
+//! every lesson they attempt. From that it works out, for each learner:
+//! - how many iterations it took to first reach Full mastery at each difficulty level
+//! - the hardest level that was held at Full mastery through the end of the run
+//! - how many iterations were spent stuck on a level without mastering it
+//! - how many times a level's mastery regressed
+//! - the area under their q value curve
+//! - with a learner model that has a true ability, how closely their q values track it
+//! - how many of the lessons recommended to them were in their zone of proximal
+//!   development, and how far the rest were from it
+//!
+//! The metrics of every learner under some strategies can then be put side by side in
+//! a `StrategyComparison` table.
+
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use serde::Serialize;
+use types::content::{DifficultyLevel, DIFFICULTY_LEVELS};
+use types::engine::{Mastery, QTableAlgorithm};
+use types::error::NeuroNudgeError;
+
+use crate::ground_truth::RecommendationFit;
+
+/// The share of the final iterations that a level must stay at Full mastery for, to
+/// count as sustained.
+const SUSTAIN_WINDOW_FRACTION: f64 = 0.1;
+/// How many consecutive attempts at a level below Full mastery it takes before a
+/// learner counts as stuck on it.
+const STUCK_WINDOW: u32 = 20;
+
+/// LearnerMetrics
+/// The learning outcomes of one learner over a simulation.
+#[derive(Debug, Clone, Serialize)]
+pub struct LearnerMetrics {
+    pub learner_id: String,
+    /// The first iteration (counting from 1) at which each level reached Full mastery,
+    /// or `None` if it never did. Ordered from easiest to hardest.
+    pub iterations_to_full_mastery: Vec<(DifficultyLevel, Option<u32>)>,
+    /// The hardest level at Full mastery for the whole of the final iterations of the
+    /// run, see `SUSTAIN_WINDOW_FRACTION`.
+    pub highest_level_sustained: Option<DifficultyLevel>,
+    /// Iterations spent on a level below Full mastery after already attempting it
+    /// `STUCK_WINDOW` times in a row.
+    pub iterations_stuck: u32,
+    /// How many times some level dropped to a lower mastery than it had before.
+    pub regressions: u32,
+    /// The mean best q value across levels, averaged over every iteration. This is the
+    /// area under the q value curve normalised to between 0 and 1.
+    pub q_value_auc: f64,
+    /// The learner's true ability at the end of the run, if the learner model has one.
+    pub final_ability: Option<f64>,
+    /// The correlation between the learner's true ability and their mean best q value
+    /// over every iteration, if the learner model has an ability.
+    pub ability_q_value_correlation: Option<f64>,
+    /// The share of recommended lessons that were in the learner's zone of proximal
+    /// development.
+    pub recommendation_accuracy: f64,
+    /// The total number of levels that recommended lessons were outside of the zone.
+    pub recommendation_regret: u32,
+}
+
+/// MetricsRecorder
+/// Records one learner's progress after every iteration of a simulation.
+#[derive(Debug, Clone)]
+pub struct MetricsRecorder {
+    learner_id: String,
+    iterations: u32,
+    first_full_mastery: Vec<Option<u32>>,
+    /// The iteration since which each level has been at Full mastery, if it is now.
+    full_mastery_since: Vec<Option<u32>>,
+    previous_mastery: Vec<Mastery>,
+    previous_level: Option<DifficultyLevel>,
+    consecutive_attempts: u32,
+    iterations_stuck: u32,
+    regressions: u32,
+    q_value_sum: f64,
+    /// (ability, mean best q value) after each iteration, if the learner has an ability.
+    ability_q_values: Vec<(f64, f64)>,
+    recommendations: u32,
+    recommendations_on_target: u32,
+    recommendation_regret: u32,
+}
+
+/// StrategyComparison
+/// The metrics of every learner under each strategy, averaged per strategy.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StrategyComparison {
+    pub rows: Vec<StrategyMetrics>,
+    /// The metrics of each learner under each strategy, for a closer look.
+    pub learners: Vec<(u8, Vec<LearnerMetrics>)>,
+}
+
+/// StrategyMetrics
+/// A row of the comparison table - a strategy's metrics averaged over its learners.
+#[derive(Debug, Clone, Serialize)]
+pub struct StrategyMetrics {
+    pub strategy: u8,
+    pub learners: usize,
+    /// How many levels each learner reached Full mastery in.
+    pub levels_reaching_full_mastery: f64,
+    /// The iterations it took to first reach Full mastery, over every level that did.
+    pub iterations_to_full_mastery: Option<f64>,
+    /// The hardest sustained level, from 1 (VeryEasy) to 8 (Grandmaster) or 0 if none.
+    pub highest_level_sustained: f64,
+    pub iterations_stuck: f64,
+    pub regressions: f64,
+    pub q_value_auc: f64,
+    /// The correlation between true ability and q values, over the learners that have
+    /// one.
+    pub ability_q_value_correlation: Option<f64>,
+    pub recommendation_accuracy: f64,
+    pub recommendation_regret: f64,
+}
+
+fn mastery_rank(mastery: &Mastery) -> u8 {
+    match mastery {
+        Mastery::None => 0,
+        Mastery::Basic => 1,
+        Mastery::Competent => 2,
+        Mastery::Full => 3,
+    }
+}
+
+/// The Pearson correlation of some pairs, or `None` if there are fewer than two pairs
+/// or either side doesn't vary.
+fn correlation(pairs: &[(f64, f64)]) -> Option<f64> {
+    if pairs.len() < 2 {
+        return None;
+    }
+    let count = pairs.len() as f64;
+    let mean_x = pairs.iter().map(|(x, _)| x).sum::<f64>() / count;
+    let mean_y = pairs.iter().map(|(_, y)| y).sum::<f64>() / count;
+
+    let mut covariance = 0.0;
+    let mut variance_x = 0.0;
+    let mut variance_y = 0.0;
+    for (x, y) in pairs {
+        covariance += (x - mean_x) * (y - mean_y);
+        variance_x += (x - mean_x).powi(2);
+        variance_y += (y - mean_y).powi(2);
+    }
+
+    if variance_x <= 0.0 || variance_y <= 0.0 {
+        return None;
+    }
+    Some(covariance / (variance_x * variance_y).sqrt())
+}
+
+impl MetricsRecorder {
+    pub fn new(learner_id: &str) -> MetricsRecorder {
+        MetricsRecorder {
+            learner_id: learner_id.to_string(),
+            iterations: 0,
+            first_full_mastery: vec![None; DIFFICULTY_LEVELS.len()],
+            full_mastery_since: vec![None; DIFFICULTY_LEVELS.len()],
+            previous_mastery: vec![Mastery::None; DIFFICULTY_LEVELS.len()],
+            previous_level: None,
+            consecutive_attempts: 0,
+            iterations_stuck: 0,
+            regressions: 0,
+            q_value_sum: 0.0,
+            ability_q_values: vec![],
+            recommendations: 0,
+            recommendations_on_target: 0,
+            recommendation_regret: 0,
+        }
+    }
+
+    /// Record the learner's q table after they attempted a lesson at some level, along
+    /// with their true ability if the learner model has one.
+    pub fn record(
+        &mut self,
+        attempted_level: &DifficultyLevel,
+        q_table: &QTableAlgorithm,
+        ability: Option<f64>,
+    ) {
+        self.iterations += 1;
+        let iteration = self.iterations;
+
+        let mut q_value_total = 0.0;
+        for (index, level) in DIFFICULTY_LEVELS.iter().enumerate() {
+            q_value_total += q_table.get_best_value_for_difficulty(level) as f64;
+
+            let mastery = q_table.get_mastery_for_difficulty(level);
+            if mastery_rank(&mastery) < mastery_rank(&self.previous_mastery[index]) {
+                self.regressions += 1;
+            }
+
+            if mastery == Mastery::Full {
+                self.first_full_mastery[index].get_or_insert(iteration);
+                self.full_mastery_since[index].get_or_insert(iteration);
+            } else {
+                self.full_mastery_since[index] = None;
+            }
+
+            self.previous_mastery[index] = mastery;
+        }
+        let mean_q_value = q_value_total / DIFFICULTY_LEVELS.len() as f64;
+        self.q_value_sum += mean_q_value;
+        if let Some(ability) = ability {
+            self.ability_q_values.push((ability, mean_q_value));
+        }
+
+        if self.previous_level.as_ref() == Some(attempted_level) {
+            self.consecutive_attempts += 1;
+        } else {
+            self.consecutive_attempts = 1;
+            self.previous_level = Some(attempted_level.clone());
+        }
+        let attempted_mastery = q_table.get_mastery_for_difficulty(attempted_level);
+        if self.consecutive_attempts > STUCK_WINDOW && attempted_mastery != Mastery::Full {
+            self.iterations_stuck += 1;
+        }
+    }
+
+    /// Record how the next lesson recommended to the learner fit their zone of proximal
+    /// development.
+    pub fn record_recommendation(&mut self, fit: RecommendationFit, regret: u32) {
+        self.recommendations += 1;
+        if fit == RecommendationFit::OnTarget {
+            self.recommendations_on_target += 1;
+        }
+        self.recommendation_regret += regret;
+    }
+
+    /// Work out the learner's metrics from everything recorded.
+    pub fn finish(self) -> LearnerMetrics {
+        let sustain_window =
+            ((self.iterations as f64 * SUSTAIN_WINDOW_FRACTION).ceil() as u32).max(1);
+        // A level is sustained if it has been at Full mastery since before the window.
+        let sustained_since = (self.iterations + 1).saturating_sub(sustain_window);
+        let highest_level_sustained = DIFFICULTY_LEVELS
+            .iter()
+            .zip(&self.full_mastery_since)
+            .rev()
+            .find(|(_, since)| since.is_some_and(|since| since <= sustained_since))
+            .map(|(level, _)| level.clone());
+
+        LearnerMetrics {
+            learner_id: self.learner_id,
+            iterations_to_full_mastery: DIFFICULTY_LEVELS
+                .iter()
+                .cloned()
+                .zip(self.first_full_mastery)
+                .collect(),
+            highest_level_sustained,
+            iterations_stuck: self.iterations_stuck,
+            regressions: self.regressions,
+            q_value_auc: if self.iterations == 0 {
+                0.0
+            } else {
+                self.q_value_sum / self.iterations as f64
+            },
+            final_ability: self.ability_q_values.last().map(|(ability, _)| *ability),
+            ability_q_value_correlation: correlation(&self.ability_q_values),
+            recommendation_accuracy: self.recommendations_on_target as f64
+                / self.recommendations.max(1) as f64,
+            recommendation_regret: self.recommendation_regret,
+        }
+    }
+}
+
+impl StrategyMetrics {
+    /// Average the metrics of every learner under some strategy.
+    pub fn new(strategy: u8, learner_metrics: &[LearnerMetrics]) -> StrategyMetrics {
+        let learners = learner_metrics.len().max(1) as f64;
+        let mean = |f: &dyn Fn(&LearnerMetrics) -> f64| {
+            learner_metrics.iter().map(f).sum::<f64>() / learners
+        };
+
+        let first_full_masteries: Vec<u32> = learner_metrics
+            .iter()
+            .flat_map(|m| m.iterations_to_full_mastery.iter())
+            .filter_map(|(_, iteration)| *iteration)
+            .collect();
+        let correlations: Vec<f64> = learner_metrics
+            .iter()
+            .filter_map(|m| m.ability_q_value_correlation)
+            .collect();
+
+        StrategyMetrics {
+            strategy,
+            learners: learner_metrics.len(),
+            levels_reaching_full_mastery: first_full_masteries.len() as f64 / learners,
+            iterations_to_full_mastery: if first_full_masteries.is_empty() {
+                None
+            } else {
+                Some(
+                    first_full_masteries.iter().map(|&i| i as f64).sum::<f64>()
+                        / first_full_masteries.len() as f64,
+                )
+            },
+            highest_level_sustained: mean(&|m| {
+                m.highest_level_sustained
+                    .as_ref()
+                    .and_then(|level| DIFFICULTY_LEVELS.iter().position(|d| d == level))
+                    .map_or(0.0, |index| (index + 1) as f64)
+            }),
+            iterations_stuck: mean(&|m| m.iterations_stuck as f64),
+            regressions: mean(&|m| m.regressions as f64),
+            q_value_auc: mean(&|m| m.q_value_auc),
+            ability_q_value_correlation: if correlations.is_empty() {
+                None
+            } else {
+                Some(correlations.iter().sum::<f64>() / correlations.len() as f64)
+            },
+            recommendation_accuracy: mean(&|m| m.recommendation_accuracy),
+            recommendation_regret: mean(&|m| m.recommendation_regret as f64),
+        }
+    }
+}
+
+impl StrategyComparison {
+    pub fn new() -> StrategyComparison {
+        StrategyComparison::default()
+    }
+
+    /// Add a row for some strategy from the metrics of its learners.
+    pub fn add_strategy(&mut self, strategy: u8, learner_metrics: Vec<LearnerMetrics>) {
+        self.rows
+            .push(StrategyMetrics::new(strategy, &learner_metrics));
+        self.learners.push((strategy, learner_metrics));
+    }
+
+    /// Write the comparison to a `.csv` file (one row per strategy), or a `.json` file
+    /// that also includes the metrics of every learner.
+    pub fn write(&self, path: &Path) -> Result<(), NeuroNudgeError> {
+        let comparison = match path.extension().and_then(|e| e.to_str()) {
+            Some("csv") => self.to_csv(),
+            Some("json") => serde_json::to_string_pretty(self)?,
+            _ => {
+                return Err(NeuroNudgeError::Serialization(format!(
+                    "{} is neither a .csv nor a .json file",
+                    path.display()
+                )))
+            }
+        };
+
+        let mut comparison_file = File::create(path)?;
+        write!(comparison_file, "{}", comparison)?;
+        Ok(())
+    }
+
+    /// Format the comparison as CSV, with one row per strategy.
+    pub fn to_csv(&self) -> String {
+        let mut csv = "strategy,learners,levels_reaching_full_mastery,iterations_to_full_mastery,highest_level_sustained,iterations_stuck,regressions,q_value_auc,ability_q_value_correlation,recommendation_accuracy,recommendation_regret\n".to_string();
+        for row in &self.rows {
+            csv += &format!(
+                "{},{},{},{},{},{},{},{},{},{},{}\n",
+                row.strategy,
+                row.learners,
+                row.levels_reaching_full_mastery,
+                row.iterations_to_full_mastery
+                    .map_or(String::new(), |i| i.to_string()),
+                row.highest_level_sustained,
+                row.iterations_stuck,
+                row.regressions,
+                row.q_value_auc,
+                row.ability_q_value_correlation
+                    .map_or(String::new(), |c| c.to_string()),
+                row.recommendation_accuracy,
+                row.recommendation_regret
+            );
+        }
+        csv
+    }
+}
+
+impl fmt::Display for StrategyComparison {
+    /// The comparison as an aligned text table.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<8} {:>8} {:>12} {:>14} {:>10} {:>10} {:>11} {:>8} {:>12} {:>10} {:>10}",
+            "Strategy",
+            "Learners",
+            "Levels Full",
+            "Iters to Full",
+            "Sustained",
+            "Stuck",
+            "Regressions",
+            "Q AUC",
+            "Ability Corr",
+            "On Target",
+            "Regret"
+        )?;
+        for row in &self.rows {
+            writeln!(
+                f,
+                "{:<8} {:>8} {:>12.2} {:>14} {:>10.2} {:>10.1} {:>11.1} {:>8.3} {:>12} {:>10.3} {:>10.1}",
+                row.strategy,
+                row.learners,
+                row.levels_reaching_full_mastery,
+                row.iterations_to_full_mastery
+                    .map_or("-".to_string(), |i| format!("{:.1}", i)),
+                row.highest_level_sustained,
+                row.iterations_stuck,
+                row.regressions,
+                row.q_value_auc,
+                row.ability_q_value_correlation
+                    .map_or("-".to_string(), |c| format!("{:.3}", c)),
+                row.recommendation_accuracy,
+                row.recommendation_regret
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::config::EngineConfig;
+    use types::engine::Strategy;
+
+    use DifficultyLevel::{Easy, Medium, VeryEasy};
+
+    /// A q table with one lesson at each of some levels, with the given q values.
+    fn q_table(values: &[(DifficultyLevel, f32)]) -> QTableAlgorithm {
+        let mut q_table =
+            QTableAlgorithm::new(None, 0.0, Strategy::BaseQLearning, EngineConfig::default())
+                .unwrap();
+        for (level, value) in values {
+            q_table.insert((format!("{level:?}"), level.clone()), *value);
+        }
+        q_table
+    }
+
+    fn record_all(
+        recorder: &mut MetricsRecorder,
+        iterations: &[(DifficultyLevel, QTableAlgorithm)],
+    ) {
+        for (attempted_level, q_table) in iterations {
+            recorder.record(attempted_level, q_table, None);
+        }
+    }
+
+    #[test]
+    fn attempts_past_the_stuck_window_without_full_mastery_are_stuck() {
+        let mut recorder = MetricsRecorder::new("learner");
+        let below_full = q_table(&[(VeryEasy, 0.3), (Easy, 0.0)]);
+        for _ in 0..STUCK_WINDOW + 5 {
+            recorder.record(&VeryEasy, &below_full, None);
+        }
+        // Moving to another level starts counting again.
+        for _ in 0..STUCK_WINDOW {
+            recorder.record(&Easy, &below_full, None);
+        }
+
+        assert_eq!(recorder.finish().iterations_stuck, 5);
+    }
+
+    #[test]
+    fn attempts_at_full_mastery_are_not_stuck() {
+        let mut recorder = MetricsRecorder::new("learner");
+        let full = q_table(&[(VeryEasy, 0.9)]);
+        for _ in 0..STUCK_WINDOW + 5 {
+            recorder.record(&VeryEasy, &full, None);
+        }
+
+        assert_eq!(recorder.finish().iterations_stuck, 0);
+    }
+
+    #[test]
+    fn each_drop_in_mastery_is_a_regression() {
+        let mut recorder = MetricsRecorder::new("learner");
+        record_all(
+            &mut recorder,
+            &[
+                (VeryEasy, q_table(&[(VeryEasy, 0.9)])),
+                // Full to Competent.
+                (VeryEasy, q_table(&[(VeryEasy, 0.75)])),
+                // Competent to None.
+                (VeryEasy, q_table(&[(VeryEasy, 0.3)])),
+                (VeryEasy, q_table(&[(VeryEasy, 0.9)])),
+            ],
+        );
+
+        let metrics = recorder.finish();
+        assert_eq!(metrics.regressions, 2);
+        assert_eq!(metrics.iterations_to_full_mastery[0], (VeryEasy, Some(1)));
+    }
+
+    #[test]
+    fn sustained_level_must_be_at_full_mastery_through_the_final_window() {
+        // Over 20 iterations the window is the final 2 iterations.
+        let mut recorder = MetricsRecorder::new("learner");
+        for iteration in 1..=20 {
+            let medium = if (5..=15).contains(&iteration) {
+                0.9
+            } else {
+                0.3
+            };
+            let easy = if iteration == 20 { 0.9 } else { 0.3 };
+            let q_table = q_table(&[(VeryEasy, 0.9), (Easy, easy), (Medium, medium)]);
+            recorder.record(&VeryEasy, &q_table, None);
+        }
+
+        let metrics = recorder.finish();
+        // Easy only reached Full mastery on the final iteration, and Medium dropped
+        // back, so only VeryEasy is sustained.
+        assert_eq!(metrics.highest_level_sustained, Some(VeryEasy));
+        assert_eq!(
+            &metrics.iterations_to_full_mastery[..3],
+            &[(VeryEasy, Some(1)), (Easy, Some(20)), (Medium, Some(5))]
+        );
+    }
+
+    #[test]
+    fn level_at_full_mastery_since_the_window_started_is_sustained() {
+        let mut recorder = MetricsRecorder::new("learner");
+        for iteration in 1..=20 {
+            let easy = if iteration >= 19 { 0.9 } else { 0.3 };
+            recorder.record(&Easy, &q_table(&[(VeryEasy, 0.9), (Easy, easy)]), None);
+        }
+
+        assert_eq!(recorder.finish().highest_level_sustained, Some(Easy));
+    }
+
+    #[test]
+    fn q_value_auc_is_the_mean_best_q_value_over_iterations() {
+        let mut recorder = MetricsRecorder::new("learner");
+        record_all(
+            &mut recorder,
+            &[
+                // A mean of 0.5 / 8 over the 8 levels.
+                (VeryEasy, q_table(&[(VeryEasy, 0.5), (Easy, 0.0)])),
+                // A mean of 1.0 / 8.
+                (Easy, q_table(&[(VeryEasy, 0.5), (Easy, 0.5)])),
+            ],
+        );
+
+        assert_eq!(recorder.finish().q_value_auc, 0.09375);
+    }
+
+    #[test]
+    fn no_iterations_have_no_q_value_auc() {
+        assert_eq!(MetricsRecorder::new("learner").finish().q_value_auc, 0.0);
+    }
+
+    #[test]
+    fn correlation_needs_two_pairs_that_vary() {
+        assert_eq!(correlation(&[]), None);
+        assert_eq!(correlation(&[(1.0, 2.0)]), None);
+        assert_eq!(correlation(&[(1.0, 2.0), (2.0, 2.0)]), None);
+        assert_eq!(
+            correlation(&[(1.0, 2.0), (2.0, 4.0), (3.0, 6.0)]),
+            Some(1.0)
+        );
+    }
+}
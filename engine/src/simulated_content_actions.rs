@@ -12,27 +12,29 @@ use types::{
     learner::{ASDTraits, Communicability, CommunicationLevel, MotorSkills},
 };
 
-/// Generates a question for copying an action.
+/// Generates a question for copying an action, with hints on how to copy it.
 fn generate_copy_action_question(
     action_description: &str,
     action_media_url: &str,
+    hints: [&str; 2],
     asd_traits_parameters: Option<ASDTraits>,
 ) -> Question {
     let prompt_text = format!("Copy this action: {}", action_description);
     Question::new(
         Prompt::new(PromptType::Video(prompt_text), action_media_url.to_string()), // Using video prompt
         None,
-        None,
-        Answer::Boolean(false), // Placeholder, actual answer to be provided by instructor
+        Some(hints.iter().map(|hint| hint.to_string()).collect()),
+        Answer::Boolean(true), // The instructor confirms whether the learner copied the action
         asd_traits_parameters,
     )
 }
 
-/// Generates a question for recognizing an action.
+/// Generates a question for recognizing an action, with hints on what to look for.
 fn generate_recognize_action_question(
     prompt: &str,
     correct_action_url: &str,
     distractors: Vec<&str>,
+    hints: [&str; 2],
     asd_traits_parameters: Option<ASDTraits>,
 ) -> Question {
     let mut options = vec![correct_action_url];
@@ -46,7 +48,7 @@ fn generate_recognize_action_question(
     Question::new(
         Prompt::new(PromptType::Text, prompt.to_string()),
         Some(question_options),
-        None,
+        Some(hints.iter().map(|hint| hint.to_string()).collect()),
         Answer::Integer(0), // Assumes the correct action is always the first
         asd_traits_parameters,
     )
@@ -65,6 +67,10 @@ pub fn generate_actions_lessons() -> Vec<Lesson> {
                     generate_copy_action_question(
                         "Clapping hands",
                         "https://example.com/clapping.gif",
+                        [
+                            "Watch how the hands meet in the middle.",
+                            "Hold your hands out in front of you, then bring them together.",
+                        ],
                         Some(ASDTraits::new(
                             "".to_string(),
                             1,
@@ -78,6 +84,10 @@ pub fn generate_actions_lessons() -> Vec<Lesson> {
                         "Which one is waving hello?",
                         "https://example.com/waving.gif",
                         vec!["https://example.com/nodding.gif"],
+                        [
+                            "Look for the hand moving from side to side.",
+                            "Waving hello is what we do when we see a friend.",
+                        ],
                         Some(ASDTraits::new(
                             "".to_string(),
                             1,
@@ -111,6 +121,10 @@ pub fn generate_actions_lessons() -> Vec<Lesson> {
                     generate_copy_action_question(
                         "Jumping",
                         "https://example.com/jumping.gif",
+                        [
+                            "Watch how the knees bend before the jump.",
+                            "Bend your knees, then push up off the floor.",
+                        ],
                         Some(asd_traits.clone()),
                     )
                 } else {
@@ -118,6 +132,10 @@ pub fn generate_actions_lessons() -> Vec<Lesson> {
                         "Which one is nodding?",
                         "https://example.com/nodding.gif",
                         vec!["https://example.com/waving.gif"],
+                        [
+                            "Look for the head moving up and down.",
+                            "Nodding is how we say yes without words.",
+                        ],
                         Some(asd_traits),
                     )
                 }
@@ -145,6 +163,10 @@ pub fn generate_actions_lessons() -> Vec<Lesson> {
                     generate_copy_action_question(
                         "Jump and Clap",
                         "https://example.com/jump_clap.gif",
+                        [
+                            "The jump comes first, then the clap.",
+                            "Try just the jump first, then add the clap.",
+                        ],
                         Some(asd_traits.clone()),
                     )
                 } else {
@@ -155,6 +177,10 @@ pub fn generate_actions_lessons() -> Vec<Lesson> {
                             "https://example.com/jump.gif",
                             "https://example.com/clap.gif",
                         ],
+                        [
+                            "Look for the person who turns all the way around.",
+                            "A spin ends facing the same way it started.",
+                        ],
                         Some(asd_traits),
                     )
                 }
@@ -182,6 +208,10 @@ pub fn generate_actions_lessons() -> Vec<Lesson> {
                     generate_copy_action_question(
                         "Dance Move",
                         "https://example.com/dance_move.gif",
+                        [
+                            "Watch the feet first, then the arms.",
+                            "Try just the first step of the move, slowly.",
+                        ],
                         Some(asd_traits.clone()),
                     )
                 } else {
@@ -192,6 +222,10 @@ pub fn generate_actions_lessons() -> Vec<Lesson> {
                             "https://example.com/step_touch.gif",
                             "https://example.com/pivot_turn.gif",
                         ],
+                        [
+                            "Look for a small kick, then a quick change of feet.",
+                            "After the kick, the foot steps down behind the other one.",
+                        ],
                         Some(asd_traits),
                     )
                 }
@@ -219,6 +253,10 @@ pub fn generate_actions_lessons() -> Vec<Lesson> {
                     generate_copy_action_question(
                         "Yoga Pose Sequence",
                         "https://example.com/yoga_pose_sequence.gif",
+                        [
+                            "Hold each pose before moving on to the next.",
+                            "Try just the first pose of the sequence.",
+                        ],
                         Some(asd_traits.clone()),
                     )
                 } else {
@@ -229,6 +267,10 @@ pub fn generate_actions_lessons() -> Vec<Lesson> {
                             "https://example.com/handstand.gif",
                             "https://example.com/forward_roll.gif",
                         ],
+                        [
+                            "Look for the person turning sideways over their hands.",
+                            "In a cartwheel the legs go over the head like the spokes of a wheel.",
+                        ],
                         Some(asd_traits),
                     )
                 }
@@ -256,6 +298,10 @@ pub fn generate_actions_lessons() -> Vec<Lesson> {
                     generate_copy_action_question(
                         "Miming an action without props",
                         "https://example.com/miming.gif",
+                        [
+                            "Watch the hands - they pretend to hold something.",
+                            "Think of what the person is pretending to use.",
+                        ],
                         Some(asd_traits.clone()),
                     )
                 } else {
@@ -266,6 +312,10 @@ pub fn generate_actions_lessons() -> Vec<Lesson> {
                             "https://example.com/looking_around.gif",
                             "https://example.com/shrugging.gif",
                         ],
+                        [
+                            "Look for a hand resting on the chin.",
+                            "Think of what you do when you are trying to remember something.",
+                        ],
                         Some(asd_traits),
                     )
                 }
@@ -293,6 +343,10 @@ pub fn generate_actions_lessons() -> Vec<Lesson> {
                     generate_copy_action_question(
                         "Complex Gymnastics Routine",
                         "https://example.com/gymnastics_routine.gif",
+                        [
+                            "Watch the routine again, one move at a time.",
+                            "Try just the first move of the routine.",
+                        ],
                         Some(asd_traits.clone()),
                     )
                 } else {
@@ -303,6 +357,10 @@ pub fn generate_actions_lessons() -> Vec<Lesson> {
                             "https://example.com/wrong_sequence_1.gif",
                             "https://example.com/wrong_sequence_2.gif",
                         ],
+                        [
+                            "Watch the first move of each video.",
+                            "Say the actions out loud in order before choosing.",
+                        ],
                         Some(asd_traits),
                     )
                 }
@@ -330,6 +388,10 @@ pub fn generate_actions_lessons() -> Vec<Lesson> {
                     generate_copy_action_question(
                         "Intricate Dance Choreography",
                         "https://example.com/advanced_dance.gif",
+                        [
+                            "Watch the choreography again, slowly.",
+                            "Try just the first eight counts.",
+                        ],
                         Some(asd_traits.clone()),
                     )
                 } else {
@@ -340,6 +402,10 @@ pub fn generate_actions_lessons() -> Vec<Lesson> {
                             "https://example.com/action_1.gif",
                             "https://example.com/action_2.gif",
                         ],
+                        [
+                            "Watch where each movement starts and stops.",
+                            "Look for the action with the cleanest finish.",
+                        ],
                         Some(asd_traits),
                     )
                 }
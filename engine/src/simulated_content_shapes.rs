@@ -16,6 +16,7 @@ fn generate_question(
     distractors: Vec<&str>,
     asd_traits: Option<ASDTraits>,
 ) -> Question {
+    let has_distractors = !distractors.is_empty();
     let mut images = vec![correct_image];
     images.extend(distractors);
 
@@ -27,12 +28,39 @@ fn generate_question(
     Question::new(
         Prompt::new(PromptType::Text, prompt.to_string()),
         Some(options),
-        None,
+        Some(generate_shape_hints(correct_image, has_distractors)),
         Answer::Integer(0), // Assumes the correct image is always the first
         asd_traits,
     )
 }
 
+/// Generates the hints for a question whose answer is the shape in `correct_image`. The
+/// first describes the shape, and the second helps to pick it out from the others.
+fn generate_shape_hints(correct_image: &str, has_distractors: bool) -> Vec<String> {
+    let (name, description) = match correct_image {
+        CIRCLE_IMAGE => ("circle", "A circle is round, with no corners."),
+        SQUARE_IMAGE => (
+            "square",
+            "A square has 4 straight sides, all the same length.",
+        ),
+        TRIANGLE_IMAGE => ("triangle", "A triangle has 3 straight sides and 3 corners."),
+        PENTAGON_IMAGE => ("pentagon", "A pentagon has 5 straight sides and 5 corners."),
+        HEXAGON_IMAGE => ("hexagon", "A hexagon has 6 straight sides and 6 corners."),
+        HEPTAGON_IMAGE => ("heptagon", "A heptagon has 7 straight sides and 7 corners."),
+        _ => ("shape", "Look closely at the shape."),
+    };
+
+    let pick_out = if !has_distractors {
+        format!("There is only one shape - tap the {}.", name)
+    } else if correct_image == CIRCLE_IMAGE {
+        "Look for the shape without any corners.".to_string()
+    } else {
+        format!("Count the corners of each shape to find the {}.", name)
+    };
+
+    vec![description.to_string(), pick_out]
+}
+
 /// Generates lessons for different difficulty levels for the "Shapes" module.
 pub fn generate_shapes_lessons() -> Vec<Lesson> {
     let mut lessons = Vec::new();
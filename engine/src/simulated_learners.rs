@@ -1,8 +1,10 @@
 //! Simulated learners for the simulation testing environment
 
 use std::collections::HashMap;
-use types::content::Lesson;
+use types::config::EngineConfig;
+use types::content::{Lesson, LessonCatalogue};
 use types::engine::{QTableAlgorithm, Strategy};
+use types::error::NeuroNudgeError;
 use types::learner::{ASDTraits, Communicability, CommunicationLevel, Learner, MotorSkills};
 
 fn generate_simulated_learner(
@@ -23,10 +25,18 @@ fn generate_simulated_learner(
     learner
 }
 
+/// The ids of the simulated learners in order, and each learner with their q table.
+pub type SimulatedLearners = (
+    Vec<&'static str>,
+    HashMap<String, (Learner, QTableAlgorithm)>,
+);
+
 pub fn generate_simulated_learners_with_q_tables(
-    lessons: &Vec<Lesson>,
+    lessons: &[Lesson],
     strategy: Strategy,
-) -> (Vec<&str>, HashMap<String, (Learner, QTableAlgorithm)>) {
+    epsilon: f32,
+    config: &EngineConfig,
+) -> Result<SimulatedLearners, NeuroNudgeError> {
     let mut simulated_learners_with_q_tables = HashMap::new();
 
     let learner_1_traits = ASDTraits::new(
@@ -78,12 +88,12 @@ pub fn generate_simulated_learners_with_q_tables(
     );
 
     // Initialise a q table for all lessons and their difficulties, with a value of 0
-    let mut q_table_1 = QTableAlgorithm::new(None, 0.3, strategy.clone());
-    let mut q_table_2 = QTableAlgorithm::new(None, 0.3, strategy.clone());
-    let mut q_table_3 = QTableAlgorithm::new(None, 0.3, strategy.clone());
-    let mut q_table_4 = QTableAlgorithm::new(None, 0.3, strategy.clone());
-    let mut q_table_5 = QTableAlgorithm::new(None, 0.3, strategy.clone());
-    let mut q_table_6 = QTableAlgorithm::new(None, 0.3, strategy.clone());
+    let mut q_table_1 = QTableAlgorithm::new(None, epsilon, strategy.clone(), config.clone())?;
+    let mut q_table_2 = QTableAlgorithm::new(None, epsilon, strategy.clone(), config.clone())?;
+    let mut q_table_3 = QTableAlgorithm::new(None, epsilon, strategy.clone(), config.clone())?;
+    let mut q_table_4 = QTableAlgorithm::new(None, epsilon, strategy.clone(), config.clone())?;
+    let mut q_table_5 = QTableAlgorithm::new(None, epsilon, strategy.clone(), config.clone())?;
+    let mut q_table_6 = QTableAlgorithm::new(None, epsilon, strategy.clone(), config.clone())?;
 
     let mut q_tables = vec![
         &mut q_table_1,
@@ -95,12 +105,17 @@ pub fn generate_simulated_learners_with_q_tables(
     ];
 
     for lesson in lessons {
-        let difficulty_level = &lesson.clone().get_difficulty_level();
+        let difficulty_level = lesson.get_difficulty_level();
         for q_table in &mut q_tables {
-            q_table.insert((lesson.clone(), difficulty_level.clone()), 0.0);
+            q_table.insert((lesson.get_id().clone(), difficulty_level.clone()), 0.0);
         }
     }
 
+    let catalogue = LessonCatalogue::new(lessons.to_vec());
+    for q_table in &mut q_tables {
+        q_table.set_lesson_prerequisites(&catalogue)?;
+    }
+
     let learners = vec![
         generate_simulated_learner("Learner 1", 7, learner_1_traits, &mut q_table_1),
         generate_simulated_learner("Learner 2", 8, learner_2_traits, &mut q_table_2),
@@ -126,7 +141,7 @@ pub fn generate_simulated_learners_with_q_tables(
         simulated_learners_with_q_tables.insert(learner_id, (learner, q_table.clone()));
     }
 
-    (
+    Ok((
         vec![
             "Learner 1",
             "Learner 2",
@@ -136,5 +151,5 @@ pub fn generate_simulated_learners_with_q_tables(
             "Learner 6",
         ],
         simulated_learners_with_q_tables,
-    )
+    ))
 }
@@ -0,0 +1,507 @@
+//! This defines the configuration of the Q Learning algorithm.
+//!
+//! Every hyperparameter that the engine uses - the learning rate and discount factor,
+//! the mastery thresholds, how the reward is weighted at each difficulty level, and how
+//! quickly q values decay - lives in an `EngineConfig`. The defaults are the values that
+//! the engine was tuned with, so `EngineConfig::default()` behaves exactly as before.
+//!
+//! With the `serde` feature enabled a config can be loaded from a TOML or JSON file.
+//! Any field left out of the file keeps its default, so a deployment only needs to list
+//! the values it wants to change, e.g.
+//!
+//! ```toml
+//! learning_rate = 0.5
+//!
+//! [mastery_thresholds]
+//! full = 0.85
+//!
+//! [non_attempt_thresholds]
+//! very_easy = 1500.0
+//! ```
+//!
+
+use crate::content::{DifficultyLevel, InstructorVerdict, PromptLevel};
+use crate::engine::Mastery;
+use crate::error::{NeuroNudgeError, Result};
+
+/// EngineConfig
+/// The hyperparameters of a q table. Each q table keeps its own copy, so that a
+/// restored snapshot continues with the config it was trained with.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct EngineConfig {
+    /// How much the value of the next lesson counts towards the value of this one.
+    pub discount_factor: f32,
+    /// How far each lesson result moves a q value towards the new estimate.
+    pub learning_rate: f32,
+    pub mastery_thresholds: MasteryThresholds,
+    /// The share of a lesson's questions that must be answered correctly without a hint
+    /// for a result to count as Full mastery. Results that would otherwise be Full
+    /// mastery are only Competent below this, as the learner still relies on hints.
+    pub full_mastery_independence: f32,
+    /// Any q value at or below this makes its difficulty level a weak level, which the
+    /// decaying strategies go back to reinforce.
+    pub weak_level_threshold: f32,
+    /// The chance of moving up a difficulty level on competent mastery.
+    pub competent_advance_probability: f32,
+    /// How much of the credit for a correct answer is kept at each prompt level.
+    pub prompt_level_credits: PromptLevelCredits,
+    /// The share of a lesson's questions that must be answered correctly on the first
+    /// try for the next recommended prompt level to fade to less support.
+    pub prompt_fade_accuracy: f32,
+    /// Below this share of questions answered correctly on the first try, the next
+    /// recommended prompt level gives more support.
+    pub prompt_support_accuracy: f32,
+    /// How much each automatically scored question counts towards the reward, relative
+    /// to `instructor_scored_weight`.
+    pub self_scored_weight: f32,
+    /// How much each question scored by an instructor counts towards the reward.
+    pub instructor_scored_weight: f32,
+    /// How much of the credit for a correct answer a Partial instructor verdict gets.
+    pub partial_verdict_credit: f32,
+    /// The mastery that every prerequisite of a lesson must reach before the lesson is
+    /// recommended.
+    pub prerequisite_mastery: Mastery,
+    /// How much a lesson result at each difficulty level is weighted in the reward.
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "partial_per_difficulty::difficulty_reward_weights")
+    )]
+    pub difficulty_reward_weights: PerDifficulty<f32>,
+    /// The (min, max) time in seconds a lesson at each difficulty level is expected
+    /// to take. Anything at or under the min gets the full time reward.
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "partial_per_difficulty::expected_time_ranges")
+    )]
+    pub expected_time_ranges: PerDifficulty<(f32, f32)>,
+    /// How many times the q values of each difficulty level can decay.
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "partial_per_difficulty::decay_counters")
+    )]
+    pub decay_counters: PerDifficulty<f32>,
+    /// How many lessons must pass without attempting a difficulty level before its
+    /// q values decay.
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "partial_per_difficulty::non_attempt_thresholds")
+    )]
+    pub non_attempt_thresholds: PerDifficulty<f32>,
+}
+
+/// MasteryThresholds
+/// The lowest value (reward or q value) that counts as each mastery level.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct MasteryThresholds {
+    pub basic: f32,
+    pub competent: f32,
+    pub full: f32,
+}
+
+/// PromptLevelCredits
+/// The share of the credit for a correct answer that is kept at each prompt level, so
+/// that the most credit goes to independent answers.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct PromptLevelCredits {
+    pub full_physical: f32,
+    pub partial_physical: f32,
+    pub model: f32,
+    pub gestural: f32,
+    pub verbal: f32,
+    pub independent: f32,
+}
+
+impl PromptLevelCredits {
+    /// Get the credit for some prompt level.
+    pub fn get(&self, prompt_level: &PromptLevel) -> f32 {
+        match prompt_level {
+            PromptLevel::FullPhysical => self.full_physical,
+            PromptLevel::PartialPhysical => self.partial_physical,
+            PromptLevel::Model => self.model,
+            PromptLevel::Gestural => self.gestural,
+            PromptLevel::Verbal => self.verbal,
+            PromptLevel::Independent => self.independent,
+        }
+    }
+}
+
+impl Default for PromptLevelCredits {
+    fn default() -> Self {
+        PromptLevelCredits {
+            full_physical: 0.2,
+            partial_physical: 0.35,
+            model: 0.5,
+            gestural: 0.65,
+            verbal: 0.8,
+            independent: 1.0,
+        }
+    }
+}
+
+/// PerDifficulty
+/// A value for each difficulty level.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PerDifficulty<T> {
+    pub very_easy: T,
+    pub easy: T,
+    pub medium: T,
+    pub hard: T,
+    pub very_hard: T,
+    pub expert: T,
+    pub master: T,
+    pub grandmaster: T,
+}
+
+impl<T> PerDifficulty<T> {
+    /// Get the value for some difficulty level.
+    pub fn get(&self, difficulty_level: &DifficultyLevel) -> &T {
+        match difficulty_level {
+            DifficultyLevel::VeryEasy => &self.very_easy,
+            DifficultyLevel::Easy => &self.easy,
+            DifficultyLevel::Medium => &self.medium,
+            DifficultyLevel::Hard => &self.hard,
+            DifficultyLevel::VeryHard => &self.very_hard,
+            DifficultyLevel::Expert => &self.expert,
+            DifficultyLevel::Master => &self.master,
+            DifficultyLevel::Grandmaster => &self.grandmaster,
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        [
+            &self.very_easy,
+            &self.easy,
+            &self.medium,
+            &self.hard,
+            &self.very_hard,
+            &self.expert,
+            &self.master,
+            &self.grandmaster,
+        ]
+        .into_iter()
+    }
+}
+
+/// PartialPerDifficulty
+/// A per-difficulty table in a config file, where each level left out keeps its
+/// default, so that `[non_attempt_thresholds] very_easy = 1500.0` only changes one level.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct PartialPerDifficulty<T> {
+    very_easy: Option<T>,
+    easy: Option<T>,
+    medium: Option<T>,
+    hard: Option<T>,
+    very_hard: Option<T>,
+    expert: Option<T>,
+    master: Option<T>,
+    grandmaster: Option<T>,
+}
+
+#[cfg(feature = "serde")]
+impl<T> PartialPerDifficulty<T> {
+    fn merge_onto(self, defaults: PerDifficulty<T>) -> PerDifficulty<T> {
+        PerDifficulty {
+            very_easy: self.very_easy.unwrap_or(defaults.very_easy),
+            easy: self.easy.unwrap_or(defaults.easy),
+            medium: self.medium.unwrap_or(defaults.medium),
+            hard: self.hard.unwrap_or(defaults.hard),
+            very_hard: self.very_hard.unwrap_or(defaults.very_hard),
+            expert: self.expert.unwrap_or(defaults.expert),
+            master: self.master.unwrap_or(defaults.master),
+            grandmaster: self.grandmaster.unwrap_or(defaults.grandmaster),
+        }
+    }
+}
+
+/// The `deserialize_with` functions of the per-difficulty fields of `EngineConfig`,
+/// which merge the levels given in the file onto the field's default.
+#[cfg(feature = "serde")]
+mod partial_per_difficulty {
+    use super::{EngineConfig, PartialPerDifficulty, PerDifficulty};
+    use serde::{Deserialize, Deserializer};
+
+    pub fn difficulty_reward_weights<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<PerDifficulty<f32>, D::Error> {
+        Ok(PartialPerDifficulty::deserialize(deserializer)?
+            .merge_onto(EngineConfig::default().difficulty_reward_weights))
+    }
+
+    pub fn expected_time_ranges<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<PerDifficulty<(f32, f32)>, D::Error> {
+        Ok(PartialPerDifficulty::deserialize(deserializer)?
+            .merge_onto(EngineConfig::default().expected_time_ranges))
+    }
+
+    pub fn decay_counters<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<PerDifficulty<f32>, D::Error> {
+        Ok(PartialPerDifficulty::deserialize(deserializer)?
+            .merge_onto(EngineConfig::default().decay_counters))
+    }
+
+    pub fn non_attempt_thresholds<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<PerDifficulty<f32>, D::Error> {
+        Ok(PartialPerDifficulty::deserialize(deserializer)?
+            .merge_onto(EngineConfig::default().non_attempt_thresholds))
+    }
+}
+
+impl Default for MasteryThresholds {
+    fn default() -> Self {
+        MasteryThresholds {
+            basic: 0.5,
+            competent: 0.7,
+            full: 0.8,
+        }
+    }
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        EngineConfig {
+            discount_factor: 0.25,
+            learning_rate: 0.75,
+            mastery_thresholds: MasteryThresholds::default(),
+            full_mastery_independence: 0.8,
+            weak_level_threshold: 0.5,
+            competent_advance_probability: 0.6,
+            prompt_level_credits: PromptLevelCredits::default(),
+            prompt_fade_accuracy: 0.8,
+            prompt_support_accuracy: 0.5,
+            self_scored_weight: 1.0,
+            instructor_scored_weight: 1.0,
+            partial_verdict_credit: 0.5,
+            prerequisite_mastery: Mastery::Competent,
+            difficulty_reward_weights: PerDifficulty {
+                very_easy: 0.2,
+                easy: 0.3,
+                medium: 0.4,
+                hard: 0.6,
+                very_hard: 0.7,
+                expert: 0.75,
+                master: 0.775,
+                grandmaster: 0.8,
+            },
+            // These match the times that the simulated learners take, see
+            // `DefaultLearnerModel` in the engine.
+            expected_time_ranges: PerDifficulty {
+                very_easy: (5.0, 10.0),
+                easy: (10.0, 15.0),
+                medium: (20.0, 30.0),
+                hard: (30.0, 40.0),
+                very_hard: (40.0, 50.0),
+                expert: (50.0, 60.0),
+                master: (60.0, 70.0),
+                grandmaster: (70.0, 80.0),
+            },
+            decay_counters: PerDifficulty {
+                very_easy: 2.0,
+                easy: 3.0,
+                medium: 4.0,
+                hard: 5.0,
+                very_hard: 6.0,
+                expert: 7.0,
+                master: 8.0,
+                grandmaster: 9.0,
+            },
+            non_attempt_thresholds: PerDifficulty {
+                very_easy: 2000.0,
+                easy: 1750.0,
+                medium: 1600.0,
+                hard: 1400.0,
+                very_hard: 1200.0,
+                expert: 1050.0,
+                master: 900.0,
+                grandmaster: 750.0,
+            },
+        }
+    }
+}
+
+impl EngineConfig {
+    /// Get the share of the credit for a correct answer that an instructor verdict gets.
+    pub fn verdict_credit(&self, verdict: &InstructorVerdict) -> f32 {
+        match verdict {
+            InstructorVerdict::Correct => 1.0,
+            InstructorVerdict::Partial => self.partial_verdict_credit,
+            InstructorVerdict::Incorrect => 0.0,
+        }
+    }
+
+    /// Check that the config can be used by the engine, e.g. that the mastery thresholds
+    /// are in order and that no expected time range is empty.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: &str| Err(NeuroNudgeError::InvalidConfig(message.to_string()));
+
+        if !(self.learning_rate > 0.0 && self.learning_rate <= 1.0) {
+            return invalid("learning_rate must be in (0, 1]");
+        }
+        if !(0.0..=1.0).contains(&self.discount_factor) {
+            return invalid("discount_factor must be in [0, 1]");
+        }
+        if !(0.0..=1.0).contains(&self.competent_advance_probability) {
+            return invalid("competent_advance_probability must be in [0, 1]");
+        }
+        if !(0.0..=1.0).contains(&self.full_mastery_independence) {
+            return invalid("full_mastery_independence must be in [0, 1]");
+        }
+
+        let credits = &self.prompt_level_credits;
+        let credits = [
+            credits.full_physical,
+            credits.partial_physical,
+            credits.model,
+            credits.gestural,
+            credits.verbal,
+            credits.independent,
+        ];
+        if credits.iter().any(|credit| !(0.0..=1.0).contains(credit)) {
+            return invalid("prompt_level_credits must all be in [0, 1]");
+        }
+        if !(0.0..=1.0).contains(&self.prompt_support_accuracy)
+            || !(0.0..=1.0).contains(&self.prompt_fade_accuracy)
+            || self.prompt_support_accuracy > self.prompt_fade_accuracy
+        {
+            return invalid(
+                "prompt_support_accuracy and prompt_fade_accuracy must satisfy 0 <= support <= fade <= 1",
+            );
+        }
+
+        let thresholds = &self.mastery_thresholds;
+        if !(thresholds.basic <= thresholds.competent && thresholds.competent <= thresholds.full) {
+            return invalid("mastery_thresholds must satisfy basic <= competent <= full");
+        }
+
+        if self.self_scored_weight < 0.0
+            || self.instructor_scored_weight < 0.0
+            || self.self_scored_weight + self.instructor_scored_weight <= 0.0
+        {
+            return invalid(
+                "self_scored_weight and instructor_scored_weight must not be negative, nor both zero",
+            );
+        }
+        if !(0.0..=1.0).contains(&self.partial_verdict_credit) {
+            return invalid("partial_verdict_credit must be in [0, 1]");
+        }
+
+        // The reward is divided by the difficulty weight and the time range, so neither
+        // can be zero.
+        if self.difficulty_reward_weights.iter().any(|&w| w <= 0.0) {
+            return invalid("difficulty_reward_weights must all be positive");
+        }
+        if self
+            .expected_time_ranges
+            .iter()
+            .any(|&(min, max)| min < 0.0 || max <= min)
+        {
+            return invalid("expected_time_ranges must each be a non-empty (min, max) range");
+        }
+        if self.decay_counters.iter().any(|&c| c < 0.0) {
+            return invalid("decay_counters must not be negative");
+        }
+        if self.non_attempt_thresholds.iter().any(|&t| t < 0.0) {
+            return invalid("non_attempt_thresholds must not be negative");
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl EngineConfig {
+    /// Parse and validate a config from TOML.
+    pub fn from_toml_str(toml: &str) -> Result<EngineConfig> {
+        let config: EngineConfig =
+            toml::from_str(toml).map_err(|e| NeuroNudgeError::Serialization(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parse and validate a config from JSON.
+    pub fn from_json_str(json: &str) -> Result<EngineConfig> {
+        let config: EngineConfig = serde_json::from_str(json)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Load a config from a `.toml` or `.json` file.
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<EngineConfig> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => EngineConfig::from_toml_str(&contents),
+            Some("json") => EngineConfig::from_json_str(&contents),
+            _ => Err(NeuroNudgeError::InvalidConfig(format!(
+                "{} is neither a .toml nor a .json file",
+                path.display()
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{QTableAlgorithm, Strategy};
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn partial_config_keeps_the_defaults() {
+        let toml = "learning_rate = 0.5\n\
+                    \n\
+                    [mastery_thresholds]\n\
+                    full = 0.85\n\
+                    \n\
+                    [non_attempt_thresholds]\n\
+                    very_easy = 1500.0\n";
+        let config = EngineConfig::from_toml_str(toml).unwrap();
+
+        let defaults = EngineConfig::default();
+        assert_eq!(config.learning_rate, 0.5);
+        assert_eq!(config.discount_factor, defaults.discount_factor);
+        assert_eq!(config.mastery_thresholds.full, 0.85);
+        assert_eq!(
+            config.mastery_thresholds.competent,
+            defaults.mastery_thresholds.competent
+        );
+        assert_eq!(config.non_attempt_thresholds.very_easy, 1500.0);
+        assert_eq!(
+            config.non_attempt_thresholds.easy,
+            defaults.non_attempt_thresholds.easy
+        );
+        assert_eq!(config.decay_counters, defaults.decay_counters);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn partial_time_ranges_keep_the_defaults() {
+        let json = r#"{ "expected_time_ranges": { "hard": [25.0, 35.0] } }"#;
+        let config = EngineConfig::from_json_str(json).unwrap();
+
+        let defaults = EngineConfig::default();
+        assert_eq!(config.expected_time_ranges.hard, (25.0, 35.0));
+        assert_eq!(
+            config.expected_time_ranges.medium,
+            defaults.expected_time_ranges.medium
+        );
+    }
+
+    #[test]
+    fn q_table_with_an_invalid_config_is_rejected() {
+        let mut config = EngineConfig::default();
+        config.expected_time_ranges.medium = (30.0, 20.0);
+        let q_table = QTableAlgorithm::new(None, 0.1, Strategy::BaseQLearning, config);
+        assert!(matches!(q_table, Err(NeuroNudgeError::InvalidConfig(_))));
+    }
+}
@@ -1,26 +1,81 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
 use uuid::Uuid;
 
+use crate::error::{NeuroNudgeError, Result};
 use crate::learner::ASDTraits;
 
+/// The namespace of every id that is derived from content, so that the same content
+/// always gets the same ids.
+const CONTENT_ID_NAMESPACE: Uuid = Uuid::from_u128(0x3f1c_2b6e_9a4d_5e07_8c21_d4a9_6b3e_f015);
+
+/// Encode the fields that identify some content as a single string. Each field is
+/// prefixed with its length, so no two different lists of fields encode the same way,
+/// whatever characters they contain.
+fn canonical_encoding<S: AsRef<str>>(fields: &[S]) -> String {
+    fields
+        .iter()
+        .map(|field| format!("{}:{}", field.as_ref().len(), field.as_ref()))
+        .collect()
+}
+
+/// Derive a stable id from the fields that identify some content, as a UUIDv5.
+fn derive_id<S: AsRef<str>>(fields: &[S]) -> String {
+    let identity = canonical_encoding(fields);
+    Uuid::new_v5(&CONTENT_ID_NAMESPACE, identity.as_bytes()).to_string()
+}
+
+/// The version of a module's content before it has ever changed.
+pub const FIRST_CONTENT_VERSION: u32 = 1;
+
 /// ContentModule
 /// A ContentmModule is a unit of study. It has a name and a list of lessons.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ContentModule {
     id: String,
     name: String,
     lessons: Vec<Lesson>,
+    /// The version of the module's lessons, which is bumped whenever they change so
+    /// that learners' q tables can be migrated to the new lessons with a
+    /// `ContentMigration`.
+    version: u32,
+    /// The ids of the modules that must be mastered before this one is started.
+    prerequisites: Vec<String>,
 }
 
 impl ContentModule {
+    /// Create a module with an id derived from its name, so that a module keeps its id
+    /// across runs and content reloads.
     pub fn new(name: String) -> ContentModule {
-        let id = Uuid::new_v4().to_string();
+        let id = derive_id(&[&name]);
+        ContentModule::new_with_id(id, name)
+    }
+
+    /// Create a module with an id of the author's choosing.
+    pub fn new_with_id(id: String, name: String) -> ContentModule {
         ContentModule {
             id,
             name,
             lessons: vec![],
+            version: FIRST_CONTENT_VERSION,
+            prerequisites: vec![],
         }
     }
 
+    /// Set the ids of the modules that must be mastered before this one is started.
+    pub fn with_prerequisites(mut self, prerequisites: Vec<String>) -> ContentModule {
+        self.prerequisites = prerequisites;
+        self
+    }
+
+    /// Set the version of the module's lessons.
+    pub fn with_version(mut self, version: u32) -> ContentModule {
+        self.version = version;
+        self
+    }
+
     pub fn with_lessons(&mut self, lessons: Vec<Lesson>) -> ContentModule {
         self.lessons = lessons;
         self.clone()
@@ -41,12 +96,67 @@ impl ContentModule {
     pub fn get_name(&self) -> &String {
         &self.name
     }
+
+    pub fn get_version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn get_prerequisites(&self) -> &Vec<String> {
+        &self.prerequisites
+    }
+}
+
+/// ContentMigration
+/// How the lessons of one version of a module became the lessons of another, so that a
+/// learner's progress can be carried over with `QTableAlgorithm::migrate`. Each old
+/// lesson that is kept is mapped to the new lesson it became, and several old lessons
+/// can be merged into one. Old lessons without a mapping were removed.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContentMigration {
+    from_version: u32,
+    to_version: u32,
+    lesson_mappings: HashMap<LessonId, LessonId>,
+}
+
+impl ContentMigration {
+    pub fn new(from_version: u32, to_version: u32) -> ContentMigration {
+        ContentMigration {
+            from_version,
+            to_version,
+            lesson_mappings: HashMap::new(),
+        }
+    }
+
+    /// Map an old lesson to the new lesson that it became.
+    pub fn with_mapping(mut self, old_lesson_id: LessonId, new_lesson_id: LessonId) -> Self {
+        self.lesson_mappings.insert(old_lesson_id, new_lesson_id);
+        self
+    }
+
+    pub fn get_from_version(&self) -> u32 {
+        self.from_version
+    }
+
+    pub fn get_to_version(&self) -> u32 {
+        self.to_version
+    }
+
+    /// Get the new lesson that an old lesson became, or `None` if it was removed.
+    pub fn get_new_lesson_id(&self, old_lesson_id: &str) -> Option<&LessonId> {
+        self.lesson_mappings.get(old_lesson_id)
+    }
+
+    pub fn get_lesson_mappings(&self) -> &HashMap<LessonId, LessonId> {
+        &self.lesson_mappings
+    }
 }
 
 /// LessonPlan
 /// A lesson plan is a set of lessons that the learner is working on. It has a name
 /// and a list of lessons.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LessonPlan {
     id: String,
     name: String,
@@ -97,6 +207,7 @@ impl LessonPlan {
 /// The difficulty level is a qualitative measure of how difficult a lesson of
 /// some module is.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DifficultyLevel {
     VeryEasy,
     Easy,
@@ -108,27 +219,51 @@ pub enum DifficultyLevel {
     Grandmaster,
 }
 
+/// All difficulty levels, from easiest to hardest.
+pub const DIFFICULTY_LEVELS: [DifficultyLevel; 8] = [
+    DifficultyLevel::VeryEasy,
+    DifficultyLevel::Easy,
+    DifficultyLevel::Medium,
+    DifficultyLevel::Hard,
+    DifficultyLevel::VeryHard,
+    DifficultyLevel::Expert,
+    DifficultyLevel::Master,
+    DifficultyLevel::Grandmaster,
+];
+
 // from str impl for difficulty level
-impl From<&str> for DifficultyLevel {
-    fn from(difficulty_level: &str) -> Self {
+impl FromStr for DifficultyLevel {
+    type Err = NeuroNudgeError;
+
+    fn from_str(difficulty_level: &str) -> Result<Self> {
         match difficulty_level {
-            "VeryEasy" => DifficultyLevel::VeryEasy,
-            "Easy" => DifficultyLevel::Easy,
-            "Medium" => DifficultyLevel::Medium,
-            "Hard" => DifficultyLevel::Hard,
-            "VeryHard" => DifficultyLevel::VeryHard,
-            "Expert" => DifficultyLevel::Expert,
-            "Master" => DifficultyLevel::Master,
-            "Grandmaster" => DifficultyLevel::Grandmaster,
-            _ => panic!("Invalid difficulty level"),
+            "VeryEasy" => Ok(DifficultyLevel::VeryEasy),
+            "Easy" => Ok(DifficultyLevel::Easy),
+            "Medium" => Ok(DifficultyLevel::Medium),
+            "Hard" => Ok(DifficultyLevel::Hard),
+            "VeryHard" => Ok(DifficultyLevel::VeryHard),
+            "Expert" => Ok(DifficultyLevel::Expert),
+            "Master" => Ok(DifficultyLevel::Master),
+            "Grandmaster" => Ok(DifficultyLevel::Grandmaster),
+            _ => Err(NeuroNudgeError::InvalidDifficultyLevel(
+                difficulty_level.to_string(),
+            )),
         }
     }
 }
 
+impl TryFrom<&str> for DifficultyLevel {
+    type Error = NeuroNudgeError;
+
+    fn try_from(difficulty_level: &str) -> Result<Self> {
+        difficulty_level.parse()
+    }
+}
+
 // into str impl for difficulty level
-impl Into<&str> for DifficultyLevel {
-    fn into(self) -> &'static str {
-        match self {
+impl From<DifficultyLevel> for &str {
+    fn from(difficulty_level: DifficultyLevel) -> Self {
+        match difficulty_level {
             DifficultyLevel::VeryEasy => "VeryEasy",
             DifficultyLevel::Easy => "Easy",
             DifficultyLevel::Medium => "Medium",
@@ -141,51 +276,131 @@ impl Into<&str> for DifficultyLevel {
     }
 }
 
+/// LessonId
+/// The id of a lesson. Lessons are referred to by their id wherever the whole lesson
+/// isn't needed, such as in the q table.
+pub type LessonId = String;
+
 /// Lesson
 /// A lesson is a unit of a lesson plam. It has a name and a list of questions that
 /// the learner requires to attempt.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Lesson {
-    id: String,
+    id: LessonId,
     name: String,
     questions: Vec<Question>,
     difficulty_level: DifficultyLevel,
     module_id: String,
+    /// The ids of the lessons of the module that must be mastered before this one is
+    /// recommended.
+    prerequisites: Vec<LessonId>,
 }
 
 impl Lesson {
+    /// Create a lesson with an id derived from its module, name and difficulty level, so
+    /// that q tables and attempt histories saved against the lesson still match it after
+    /// a restart or content reload. Its questions are given derived ids too, see
+    /// `with_derived_question_ids`.
     pub fn new(
         name: String,
         questions: Vec<Question>,
         difficulty_level: DifficultyLevel,
         module_id: String,
     ) -> Lesson {
-        let id = Uuid::new_v4().to_string();
+        let difficulty: &str = difficulty_level.clone().into();
+        let id = derive_id(&[&module_id, &name, difficulty]);
+        Lesson::new_with_id(id, name, questions, difficulty_level, module_id)
+            .with_derived_question_ids()
+    }
+
+    /// Create a lesson with an id of the author's choosing. The questions keep their
+    /// own ids - see `with_derived_question_ids` to derive those instead.
+    pub fn new_with_id(
+        id: LessonId,
+        name: String,
+        questions: Vec<Question>,
+        difficulty_level: DifficultyLevel,
+        module_id: String,
+    ) -> Lesson {
         Lesson {
             id,
             name,
             questions,
             difficulty_level,
             module_id,
+            prerequisites: vec![],
         }
     }
 
-    pub fn get_asd_traits_parameters(&self) -> ASDTraits {
-        self.questions[0]
+    /// Set the ids of the lessons of the module that must be mastered before this one
+    /// is recommended.
+    pub fn with_prerequisites(mut self, prerequisites: Vec<LessonId>) -> Lesson {
+        self.prerequisites = prerequisites;
+        self
+    }
+
+    /// Get the ASD traits parameters of the lesson, which are those of its first question.
+    /// Questions can each have their own parameters, so use `Question::get_asd_traits_parameters`
+    /// wherever the question is known.
+    pub fn get_asd_traits_parameters(&self) -> Result<&ASDTraits> {
+        let first_question =
+            self.questions
+                .first()
+                .ok_or_else(|| NeuroNudgeError::EmptyLesson {
+                    lesson_id: self.id.clone(),
+                })?;
+
+        first_question
             .get_asd_traits_parameters()
-            .clone()
-            .unwrap()
+            .as_ref()
+            .ok_or_else(|| NeuroNudgeError::MissingAsdTraits {
+                question_id: first_question.get_id().clone(),
+            })
     }
 
-    pub fn add_question(&mut self, question: Question) {
+    /// Add a question to the end of the lesson, with an id derived the same way as
+    /// those of `with_derived_question_ids`.
+    pub fn add_question(&mut self, mut question: Question) {
+        let content = question.content_identity();
+        let occurrence = self
+            .questions
+            .iter()
+            .filter(|q| q.content_identity() == content)
+            .count();
+        question.set_id(self.derive_question_id(&content, occurrence));
         self.questions.push(question);
     }
 
+    /// Derive the id of a question from the lesson's id, the question's content and how
+    /// many identical questions come before it in the lesson.
+    fn derive_question_id(&self, content: &str, occurrence: usize) -> String {
+        derive_id(&[self.id.as_str(), content, &occurrence.to_string()])
+    }
+
+    /// Give every question an id derived from the lesson's id and the question's
+    /// content - its prompt, options and answer - and every option an id derived from
+    /// its question's. Hints and ASD traits aren't part of a question's identity, so they
+    /// can be changed without losing the question's attempt history. Identical
+    /// questions in the lesson are told apart by how many come before them.
+    pub fn with_derived_question_ids(mut self) -> Lesson {
+        let mut seen_questions: HashMap<String, usize> = HashMap::new();
+        let mut questions = std::mem::take(&mut self.questions);
+        for question in &mut questions {
+            let content = question.content_identity();
+            let occurrence = seen_questions.entry(content.clone()).or_insert(0);
+            question.set_id(self.derive_question_id(&content, *occurrence));
+            *occurrence += 1;
+        }
+        self.questions = questions;
+        self
+    }
+
     pub fn get_questions(&self) -> &Vec<Question> {
         &self.questions
     }
 
-    pub fn get_id(&self) -> &String {
+    pub fn get_id(&self) -> &LessonId {
         &self.id
     }
 
@@ -197,8 +412,93 @@ impl Lesson {
         &self.name
     }
 
-    pub fn get_difficulty_level(self) -> DifficultyLevel {
-        self.difficulty_level
+    pub fn get_difficulty_level(&self) -> &DifficultyLevel {
+        &self.difficulty_level
+    }
+
+    pub fn get_prerequisites(&self) -> &Vec<LessonId> {
+        &self.prerequisites
+    }
+
+    /// Get the difficulty level of one of the lesson's questions, which is the lesson's
+    /// own unless the question has its own.
+    pub fn get_question_difficulty_level<'a>(
+        &'a self,
+        question: &'a Question,
+    ) -> &'a DifficultyLevel {
+        question
+            .get_difficulty_level()
+            .as_ref()
+            .unwrap_or(&self.difficulty_level)
+    }
+}
+
+/// LessonCatalogue
+/// A lesson catalogue holds a set of lessons that can be looked up by their id. The
+/// q table only refers to lessons by id, so the catalogue is how an action chosen by
+/// the engine is resolved back into the lesson that the learner should do.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LessonCatalogue {
+    lessons: Vec<Lesson>,
+    /// Position of each lesson in `lessons`, by lesson id.
+    positions: HashMap<LessonId, usize>,
+}
+
+impl LessonCatalogue {
+    pub fn new(lessons: Vec<Lesson>) -> LessonCatalogue {
+        let mut catalogue = LessonCatalogue::default();
+        for lesson in lessons {
+            catalogue.add_lesson(lesson);
+        }
+        catalogue
+    }
+
+    /// Add a lesson to the catalogue, replacing any lesson with the same id.
+    pub fn add_lesson(&mut self, lesson: Lesson) {
+        match self.positions.get(lesson.get_id()) {
+            Some(&position) => self.lessons[position] = lesson,
+            None => {
+                self.positions
+                    .insert(lesson.get_id().clone(), self.lessons.len());
+                self.lessons.push(lesson);
+            }
+        }
+    }
+
+    pub fn get(&self, lesson_id: &str) -> Option<&Lesson> {
+        self.positions
+            .get(lesson_id)
+            .map(|&position| &self.lessons[position])
+    }
+
+    /// Get a lesson by its id, failing if it isn't in the catalogue.
+    pub fn try_get(&self, lesson_id: &str) -> Result<&Lesson> {
+        self.get(lesson_id)
+            .ok_or_else(|| NeuroNudgeError::LessonNotFound(lesson_id.to_string()))
+    }
+
+    pub fn contains(&self, lesson_id: &str) -> bool {
+        self.positions.contains_key(lesson_id)
+    }
+
+    /// Get all lessons in the order that they were added.
+    pub fn get_lessons(&self) -> &Vec<Lesson> {
+        &self.lessons
+    }
+
+    pub fn len(&self) -> usize {
+        self.lessons.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lessons.is_empty()
+    }
+}
+
+impl From<&ContentModule> for LessonCatalogue {
+    fn from(module: &ContentModule) -> Self {
+        LessonCatalogue::new(module.get_lessons().clone())
     }
 }
 
@@ -206,6 +506,7 @@ impl Lesson {
 /// A question option is an option that the learner can select as an answer to a question.
 /// This could be text or an image.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum QuestionOptionType {
     Text,
     Image,
@@ -214,6 +515,7 @@ pub enum QuestionOptionType {
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct QuestionOption {
     id: String,
     option: String,
@@ -223,6 +525,15 @@ pub struct QuestionOption {
 impl QuestionOption {
     pub fn new(option: String, option_type: QuestionOptionType) -> QuestionOption {
         let id = Uuid::new_v4().to_string();
+        QuestionOption::new_with_id(id, option, option_type)
+    }
+
+    /// Create an option with an id of the author's choosing.
+    pub fn new_with_id(
+        id: String,
+        option: String,
+        option_type: QuestionOptionType,
+    ) -> QuestionOption {
         QuestionOption {
             id,
             option,
@@ -251,6 +562,7 @@ impl QuestionOption {
 /// - a question that requires the learner to imitate the prompt (such as an action) and the instructor
 ///   will determine if the learner has done it correctly
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Question {
     id: String,
     prompt: Prompt,
@@ -261,6 +573,8 @@ pub struct Question {
     /// which a learner needs to have for optimal success. If they aren't at the
     /// level of these parameters, their chances of correctness will be lowered.
     asd_traits_parameters: Option<ASDTraits>,
+    /// How difficult this question is, if it differs from the rest of its lesson.
+    difficulty_level: Option<DifficultyLevel>,
 }
 
 impl Question {
@@ -272,7 +586,19 @@ impl Question {
         asd_traits_parameters: Option<ASDTraits>,
     ) -> Question {
         let id = Uuid::new_v4().to_string();
+        Question::new_with_id(id, prompt, options, hints, answer, asd_traits_parameters)
+    }
 
+    /// Create a question with an id of the author's choosing. Its options keep their
+    /// own ids.
+    pub fn new_with_id(
+        id: String,
+        prompt: Prompt,
+        options: Option<Vec<QuestionOption>>,
+        hints: Option<Vec<String>>,
+        answer: Answer,
+        asd_traits_parameters: Option<ASDTraits>,
+    ) -> Question {
         Question {
             id,
             prompt,
@@ -280,13 +606,96 @@ impl Question {
             hints,
             options,
             asd_traits_parameters,
+            difficulty_level: None,
         }
     }
 
+    /// Set the question's own difficulty level, e.g. for a harder question in an
+    /// otherwise easy lesson.
+    pub fn with_difficulty_level(mut self, difficulty_level: DifficultyLevel) -> Question {
+        self.difficulty_level = Some(difficulty_level);
+        self
+    }
+
+    /// Set the question's id, and derive the ids of its options from it and their
+    /// position.
+    fn set_id(&mut self, id: String) {
+        if let Some(options) = &mut self.options {
+            for (index, option) in options.iter_mut().enumerate() {
+                option.id = derive_id(&[id.as_str(), &index.to_string()]);
+            }
+        }
+        self.id = id;
+    }
+
+    /// What the question asks and expects as its answer, which is what identifies it.
+    /// Every kind of prompt, option and answer is written with a fixed name, rather
+    /// than its name in Rust, so that renaming a type doesn't change any question's id.
+    fn content_identity(&self) -> String {
+        let mut fields: Vec<String> = vec![];
+        match &self.prompt.prompt_type {
+            PromptType::Text => fields.push("text".to_string()),
+            PromptType::Image => fields.push("image".to_string()),
+            PromptType::Video(instruction) => {
+                fields.extend(["video".to_string(), instruction.clone()])
+            }
+        }
+        fields.push(self.prompt.prompt.clone());
+
+        let options = self.options.as_deref().unwrap_or_default();
+        fields.push(options.len().to_string());
+        for option in options {
+            let option_type = match option.option_type {
+                QuestionOptionType::Text => "text",
+                QuestionOptionType::Image => "image",
+                QuestionOptionType::Video => "video",
+                QuestionOptionType::Audio => "audio",
+            };
+            fields.extend([option_type.to_string(), option.option.clone()]);
+        }
+
+        let indices = |indices: &[u8]| -> Vec<String> {
+            let mut fields = vec![indices.len().to_string()];
+            fields.extend(indices.iter().map(|index| index.to_string()));
+            fields
+        };
+        match &self.answer {
+            Answer::Integer(index) => fields.extend(["integer".to_string(), index.to_string()]),
+            Answer::Boolean(confirmation) => {
+                fields.extend(["boolean".to_string(), confirmation.to_string()])
+            }
+            Answer::MultiSelect(selections) => {
+                fields.push("multi_select".to_string());
+                fields.extend(indices(selections));
+            }
+            Answer::Ordering(order) => {
+                fields.push("ordering".to_string());
+                fields.extend(indices(order));
+            }
+            Answer::Matching(pairs) => {
+                fields.push("matching".to_string());
+                let flattened: Vec<u8> = pairs.iter().flat_map(|&(a, b)| [a, b]).collect();
+                fields.extend(indices(&flattened));
+            }
+            Answer::FreeText(accepted) => {
+                fields.extend(["free_text".to_string(), accepted.len().to_string()]);
+                fields.extend(accepted.iter().cloned());
+            }
+        }
+
+        canonical_encoding(&fields)
+    }
+
     pub fn get_asd_traits_parameters(&self) -> &Option<ASDTraits> {
         &self.asd_traits_parameters
     }
 
+    /// Get the question's own difficulty level, if it has one. See
+    /// `Lesson::get_question_difficulty_level` for the level it is attempted at.
+    pub fn get_difficulty_level(&self) -> &Option<DifficultyLevel> {
+        &self.difficulty_level
+    }
+
     pub fn add_hint(&mut self, hint: String) {
         match &mut self.hints {
             Some(hints) => {
@@ -317,11 +726,279 @@ impl Question {
     pub fn get_hints(&self) -> &Option<Vec<String>> {
         &self.hints
     }
+
+    /// Whether the learner's response is judged by an instructor rather than scored
+    /// automatically, as with imitation questions.
+    pub fn is_instructor_scored(&self) -> bool {
+        matches!(self.answer, Answer::Boolean(_))
+    }
+
+    /// Check that the question's answer makes sense with its options. Answers that pick
+    /// out options must only use indices of its options (each at most once), an ordering
+    /// must use every option, and a question confirmed by an instructor or answered in
+    /// free text can't have options to choose from.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |reason: String| {
+            Err(NeuroNudgeError::InvalidQuestion {
+                question_id: self.id.clone(),
+                reason,
+            })
+        };
+        let total_options = self.options.as_ref().map_or(0, |options| options.len());
+
+        let option_indices: Vec<u8> = match &self.answer {
+            Answer::Integer(index) => vec![*index],
+            Answer::MultiSelect(indices) | Answer::Ordering(indices) => indices.clone(),
+            Answer::Matching(pairs) => pairs.iter().flat_map(|&(a, b)| [a, b]).collect(),
+            Answer::Boolean(_) | Answer::FreeText(_) => {
+                if total_options > 0 {
+                    return invalid(
+                        "an instructor confirmed or free text answer can't have options to choose from"
+                            .to_string(),
+                    );
+                }
+                vec![]
+            }
+        };
+
+        match &self.answer {
+            Answer::Integer(_)
+            | Answer::MultiSelect(_)
+            | Answer::Ordering(_)
+            | Answer::Matching(_)
+                if total_options == 0 =>
+            {
+                return invalid("an answer that picks out options needs options".to_string());
+            }
+            Answer::MultiSelect(indices) | Answer::Ordering(indices) if indices.is_empty() => {
+                return invalid("the answer must pick out at least one option".to_string());
+            }
+            Answer::Matching(pairs) if pairs.is_empty() => {
+                return invalid("the answer must have at least one matching pair".to_string());
+            }
+            Answer::Ordering(indices) if indices.len() != total_options => {
+                return invalid(format!(
+                    "an ordering must use all {} options, but uses {}",
+                    total_options,
+                    indices.len()
+                ));
+            }
+            Answer::FreeText(accepted) if accepted.iter().all(|a| normalise_text(a).is_empty()) => {
+                return invalid(
+                    "a free text answer needs at least one accepted answer".to_string(),
+                );
+            }
+            _ => {}
+        }
+
+        if let Some(index) = option_indices
+            .iter()
+            .find(|&&i| i as usize >= total_options)
+        {
+            return invalid(format!(
+                "answer index {} is out of range of its {} options",
+                index, total_options
+            ));
+        }
+        if has_duplicates(&option_indices) {
+            return invalid("the answer uses an option more than once".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Evaluate a response to the question. The question is validated first, and the
+    /// response must be of the kind the question expects, e.g. an option index for a
+    /// multiple choice question or an instructor's confirmation for an imitation one.
+    ///
+    /// Responses to multi-select, ordering and matching questions can earn partial
+    /// credit:
+    /// - multi-select: the correct options selected, less the incorrect ones, out of
+    ///   all the correct options
+    /// - ordering: the share of options in their correct position
+    /// - matching: the share of the answer's pairs that were matched
+    pub fn evaluate(&self, response: &Response) -> Result<ResponseEvaluation> {
+        self.validate()?;
+        let invalid = |reason: String| {
+            Err(NeuroNudgeError::InvalidResponse {
+                question_id: self.id.clone(),
+                reason,
+            })
+        };
+        let options = self.options.as_deref().unwrap_or_default();
+
+        // Every option index in the response must be in range, and used at most once.
+        let response_indices: Vec<u8> = match response {
+            Response::Selection(index) => vec![*index],
+            Response::Selections(indices) | Response::Order(indices) => indices.clone(),
+            Response::Matches(pairs) => pairs.iter().flat_map(|&(a, b)| [a, b]).collect(),
+            Response::InstructorConfirmation(_) | Response::Text(_) => vec![],
+        };
+        if let Some(index) = response_indices
+            .iter()
+            .find(|&&i| i as usize >= options.len())
+        {
+            return invalid(format!(
+                "option {} is out of range of the {} options",
+                index,
+                options.len()
+            ));
+        }
+        if has_duplicates(&response_indices) {
+            return invalid("the response uses an option more than once".to_string());
+        }
+
+        let (credit, selected_option_id) = match (&self.answer, response) {
+            (Answer::Integer(answer), Response::Selection(index)) => (
+                if index == answer { 1.0 } else { 0.0 },
+                Some(options[*index as usize].get_id().clone()),
+            ),
+            (Answer::Boolean(answer), Response::InstructorConfirmation(confirmation)) => {
+                (if confirmation == answer { 1.0 } else { 0.0 }, None)
+            }
+            (Answer::MultiSelect(answer), Response::Selections(selected)) => {
+                let correct = selected.iter().filter(|i| answer.contains(i)).count() as f32;
+                let incorrect = selected.len() as f32 - correct;
+                (((correct - incorrect) / answer.len() as f32).max(0.0), None)
+            }
+            (Answer::Ordering(answer), Response::Order(order)) => {
+                if order.len() != answer.len() {
+                    return invalid(format!(
+                        "an ordering must use all {} options, but uses {}",
+                        answer.len(),
+                        order.len()
+                    ));
+                }
+                let in_place = order.iter().zip(answer).filter(|(a, b)| a == b).count();
+                (in_place as f32 / answer.len() as f32, None)
+            }
+            (Answer::Matching(answer), Response::Matches(matches)) => {
+                // A pair matches either way round.
+                let matched = answer
+                    .iter()
+                    .filter(|&&(a, b)| matches.contains(&(a, b)) || matches.contains(&(b, a)))
+                    .count();
+                (matched as f32 / answer.len() as f32, None)
+            }
+            (Answer::FreeText(accepted), Response::Text(text)) => {
+                let text = normalise_text(text);
+                let is_accepted = accepted.iter().any(|a| normalise_text(a) == text);
+                (if is_accepted { 1.0 } else { 0.0 }, None)
+            }
+            (answer, response) => {
+                return invalid(format!(
+                    "a {} response can't answer a {} question",
+                    response.kind(),
+                    answer.kind()
+                ))
+            }
+        };
+
+        Ok(ResponseEvaluation {
+            question_id: self.id.clone(),
+            is_correct: credit >= 1.0,
+            credit,
+            selected_option_id,
+            instructor_scored: self.is_instructor_scored(),
+        })
+    }
+}
+
+/// Normalise free text for comparison - lower case, without punctuation, and with
+/// words separated by single spaces.
+fn normalise_text(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn has_duplicates(indices: &[u8]) -> bool {
+    indices
+        .iter()
+        .enumerate()
+        .any(|(position, index)| indices[..position].contains(index))
+}
+
+/// Response
+/// A response to a question. Each kind of response answers the matching kind of
+/// `Answer`:
+/// - `Selection`: the index of the option the learner selected
+/// - `InstructorConfirmation`: for questions judged by an instructor, whether the
+///   instructor confirms the learner responded as the answer expects
+/// - `Selections`: the indices of every option the learner selected
+/// - `Order`: option indices in the order the learner put them
+/// - `Matches`: pairs of option indices that the learner matched together
+/// - `Text`: a short answer, typed in by the instructor for verbal answers
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Response {
+    Selection(u8),
+    InstructorConfirmation(bool),
+    Selections(Vec<u8>),
+    Order(Vec<u8>),
+    Matches(Vec<(u8, u8)>),
+    Text(String),
+}
+
+impl Response {
+    fn kind(&self) -> &'static str {
+        match self {
+            Response::Selection(_) => "selection",
+            Response::InstructorConfirmation(_) => "instructor confirmation",
+            Response::Selections(_) => "multi-select",
+            Response::Order(_) => "ordering",
+            Response::Matches(_) => "matching",
+            Response::Text(_) => "free text",
+        }
+    }
+}
+
+/// ResponseEvaluation
+/// The outcome of evaluating a response to a question.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResponseEvaluation {
+    question_id: String,
+    is_correct: bool,
+    /// The share of full credit that the response earns, from 0 to 1. Only a fully
+    /// correct response gets 1.
+    credit: f32,
+    /// The id of the option that the learner selected, for multiple choice questions.
+    selected_option_id: Option<String>,
+    /// Whether the response was judged by an instructor.
+    instructor_scored: bool,
+}
+
+impl ResponseEvaluation {
+    pub fn get_question_id(&self) -> &String {
+        &self.question_id
+    }
+
+    pub fn is_correct(&self) -> bool {
+        self.is_correct
+    }
+
+    pub fn get_credit(&self) -> f32 {
+        self.credit
+    }
+
+    pub fn get_selected_option_id(&self) -> &Option<String> {
+        &self.selected_option_id
+    }
+
+    pub fn is_instructor_scored(&self) -> bool {
+        self.instructor_scored
+    }
 }
 
 /// Prompt
 /// A prompt is the question that is asked of the learner. It could be an image, a video, or simply text.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Prompt {
     prompt_type: PromptType,
     prompt: String,
@@ -347,6 +1024,7 @@ impl Prompt {
 /// PromptType
 /// The type of prompt that is being used. This could be an image, a video, or simply text.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PromptType {
     Image,
     Video(String), // The string is the textual instruction attached to the video.
@@ -357,11 +1035,129 @@ pub enum PromptType {
 /// An answer is the response that the learner provides to the question. If the question requires
 /// the instructor to confirm, then we expect a true or false response from the instructor.
 /// Otherwise, we expect an integer response from the learner which is the index of the answer
-/// that they have selected.
+/// that they have selected, or one of the richer kinds of answer below. Indices are always
+/// into the question's options.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Answer {
     Integer(u8),
     Boolean(bool),
+    /// Every option that should be selected, e.g. "select all the circles".
+    MultiSelect(Vec<u8>),
+    /// Every option in its correct order, e.g. "put these steps in order".
+    Ordering(Vec<u8>),
+    /// Pairs of options that go together, e.g. "match the shape to its name".
+    Matching(Vec<(u8, u8)>),
+    /// The accepted answers to a short verbal question. Responses are compared
+    /// ignoring case, punctuation and extra spaces.
+    FreeText(Vec<String>),
+}
+
+impl Answer {
+    fn kind(&self) -> &'static str {
+        match self {
+            Answer::Integer(_) => "multiple choice",
+            Answer::Boolean(_) => "instructor confirmed",
+            Answer::MultiSelect(_) => "multi-select",
+            Answer::Ordering(_) => "ordering",
+            Answer::Matching(_) => "matching",
+            Answer::FreeText(_) => "free text",
+        }
+    }
+}
+
+/// PromptLevel
+/// The level of prompting that an instructor gave for an attempt, as per the ABA
+/// (applied behaviour analysis) prompting hierarchy. Levels are ordered from the most
+/// support (FullPhysical) to none at all (Independent).
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PromptLevel {
+    /// The instructor physically guides the learner through the whole response.
+    FullPhysical,
+    /// The instructor physically guides the learner through part of the response.
+    PartialPhysical,
+    /// The instructor demonstrates the response for the learner to imitate.
+    Model,
+    /// The instructor points, nods or otherwise gestures towards the response.
+    Gestural,
+    /// The instructor tells the learner the response, or part of it.
+    Verbal,
+    /// The learner responds without any prompting.
+    Independent,
+}
+
+/// All prompt levels, from the most support to none at all.
+pub const PROMPT_LEVELS: [PromptLevel; 6] = [
+    PromptLevel::FullPhysical,
+    PromptLevel::PartialPhysical,
+    PromptLevel::Model,
+    PromptLevel::Gestural,
+    PromptLevel::Verbal,
+    PromptLevel::Independent,
+];
+
+impl PromptLevel {
+    /// The next level with less support, fading the prompt towards independence.
+    pub fn fade(&self) -> PromptLevel {
+        let index = PROMPT_LEVELS.iter().position(|p| p == self).unwrap_or(0);
+        PROMPT_LEVELS[(index + 1).min(PROMPT_LEVELS.len() - 1)]
+    }
+
+    /// The next level with more support.
+    pub fn support(&self) -> PromptLevel {
+        let index = PROMPT_LEVELS.iter().position(|p| p == self).unwrap_or(0);
+        PROMPT_LEVELS[index.saturating_sub(1)]
+    }
+}
+
+/// InstructorVerdict
+/// An instructor's judgement of a learner's response.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InstructorVerdict {
+    Correct,
+    /// The response was partly right, e.g. an action that was only partly copied.
+    Partial,
+    Incorrect,
+}
+
+/// InstructorEvaluation
+/// An instructor's evaluation of a learner's response to a question that they judge,
+/// such as copying an action.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InstructorEvaluation {
+    verdict: InstructorVerdict,
+    notes: Option<String>,
+    /// The id of the instructor who evaluated the response.
+    evaluator_id: String,
+}
+
+impl InstructorEvaluation {
+    pub fn new(
+        verdict: InstructorVerdict,
+        notes: Option<String>,
+        evaluator_id: String,
+    ) -> InstructorEvaluation {
+        InstructorEvaluation {
+            verdict,
+            notes,
+            evaluator_id,
+        }
+    }
+
+    pub fn get_verdict(&self) -> &InstructorVerdict {
+        &self.verdict
+    }
+
+    pub fn get_notes(&self) -> &Option<String> {
+        &self.notes
+    }
+
+    pub fn get_evaluator_id(&self) -> &String {
+        &self.evaluator_id
+    }
 }
 
 /// QuestionAttempt
@@ -371,14 +1167,23 @@ pub enum Answer {
 /// - number of total attempts
 /// - number of incorrect attempts
 /// - number of hints requested (if relevant, might be irrelevant for a question)
+/// - the level of prompting the instructor gave (if recorded)
+/// - the instructor's evaluation, for questions that an instructor judges
+///
 /// Based on the above factors, the engine will determine the learner's progress and make recommendations.
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct QuestionAttempt {
     question_id: String,
     time_taken: i32,
     total_attempts: i32,
     incorrect_attempts: i32,
     hints_requested: Option<i32>,
+    prompt_level: Option<PromptLevel>,
+    /// Set for questions that are scored by an instructor rather than automatically.
+    instructor_evaluation: Option<InstructorEvaluation>,
+    /// The partial credit that the final response earned, for questions that give it.
+    credit: Option<f32>,
 }
 
 impl QuestionAttempt {
@@ -394,9 +1199,35 @@ impl QuestionAttempt {
             total_attempts,
             incorrect_attempts,
             hints_requested: None,
+            prompt_level: None,
+            instructor_evaluation: None,
+            credit: None,
         }
     }
 
+    /// Record the level of prompting that the instructor gave for the attempt.
+    pub fn with_prompt_level(mut self, prompt_level: PromptLevel) -> QuestionAttempt {
+        self.prompt_level = Some(prompt_level);
+        self
+    }
+
+    /// Record the partial credit that the final response earned, from its
+    /// `ResponseEvaluation`.
+    pub fn with_credit(mut self, credit: f32) -> QuestionAttempt {
+        self.credit = Some(credit.clamp(0.0, 1.0));
+        self
+    }
+
+    /// Record the instructor's evaluation of the response, making this an instructor
+    /// scored attempt.
+    pub fn with_instructor_evaluation(
+        mut self,
+        instructor_evaluation: InstructorEvaluation,
+    ) -> QuestionAttempt {
+        self.instructor_evaluation = Some(instructor_evaluation);
+        self
+    }
+
     pub fn increment_hints_requested(&mut self) {
         match &mut self.hints_requested {
             Some(hints_requested) => {
@@ -427,6 +1258,39 @@ impl QuestionAttempt {
     pub fn get_hints_requested(&self) -> &Option<i32> {
         &self.hints_requested
     }
+
+    pub fn get_prompt_level(&self) -> &Option<PromptLevel> {
+        &self.prompt_level
+    }
+
+    pub fn get_instructor_evaluation(&self) -> &Option<InstructorEvaluation> {
+        &self.instructor_evaluation
+    }
+
+    pub fn get_credit(&self) -> &Option<f32> {
+        &self.credit
+    }
+
+    /// Whether the attempt was scored by an instructor rather than automatically.
+    pub fn is_instructor_scored(&self) -> bool {
+        self.instructor_evaluation.is_some()
+    }
+
+    /// Whether the question was eventually answered correctly. For a question scored by
+    /// an instructor, this is whether they gave a Correct verdict.
+    pub fn is_correct(&self) -> bool {
+        match &self.instructor_evaluation {
+            Some(evaluation) => evaluation.get_verdict() == &InstructorVerdict::Correct,
+            None => self.total_attempts > self.incorrect_attempts,
+        }
+    }
+
+    /// Whether the question was answered correctly without any hints or prompting.
+    pub fn is_independent(&self) -> bool {
+        self.is_correct()
+            && self.hints_requested.unwrap_or(0) == 0
+            && self.prompt_level.unwrap_or(PromptLevel::Independent) == PromptLevel::Independent
+    }
 }
 
 /// LessonResult
@@ -434,7 +1298,8 @@ impl QuestionAttempt {
 /// - time taken to complete the lesson
 /// - total number of questions in the lesson
 /// - a list of question attempts
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LessonResult {
     difficulty_level: DifficultyLevel,
     time_taken: i32,
@@ -466,7 +1331,7 @@ impl LessonResult {
     }
 
     pub fn get_time_taken(&self) -> i32 {
-        self.time_taken.clone()
+        self.time_taken
     }
 
     pub fn get_total_incorrect_attempts(&self) -> i32 {
@@ -474,29 +1339,81 @@ impl LessonResult {
         for question_attempt in &self.attempted_questions {
             total_incorrect_attempts += question_attempt.get_incorrect_attempts();
         }
-        total_incorrect_attempts.clone()
+        total_incorrect_attempts
     }
 
     pub fn get_total_hints_requested(&self) -> i32 {
         let mut total_hints_requested = 0;
         for question_attempt in &self.attempted_questions {
-            match question_attempt.get_hints_requested() {
-                Some(hints_requested) => {
-                    total_hints_requested += hints_requested;
-                }
-                None => {}
+            if let Some(hints_requested) = question_attempt.get_hints_requested() {
+                total_hints_requested += hints_requested;
             }
         }
-        total_hints_requested.clone()
+        total_hints_requested
     }
 
     pub fn get_total_questions(&self) -> &i32 {
         &self.total_questions
     }
 
+    /// The share of attempted questions that were answered correctly without any hints,
+    /// or 1 if no questions were attempted.
+    pub fn get_independent_answer_share(&self) -> f32 {
+        if self.attempted_questions.is_empty() {
+            return 1.0;
+        }
+
+        let independent_answers = self
+            .attempted_questions
+            .iter()
+            .filter(|question_attempt| question_attempt.is_independent())
+            .count();
+        independent_answers as f32 / self.attempted_questions.len() as f32
+    }
+
+    /// The mean partial credit over the attempted questions, where an attempt without
+    /// any recorded credit gets full credit if it was answered correctly. Returns 1 if
+    /// no questions were attempted.
+    pub fn get_mean_credit(&self) -> f32 {
+        if self.attempted_questions.is_empty() {
+            return 1.0;
+        }
+
+        let total_credit: f32 = self
+            .attempted_questions
+            .iter()
+            .map(|question_attempt| {
+                question_attempt
+                    .get_credit()
+                    .unwrap_or(if question_attempt.is_correct() {
+                        1.0
+                    } else {
+                        0.0
+                    })
+            })
+            .sum();
+        total_credit / self.attempted_questions.len() as f32
+    }
+
     pub fn get_attempted_questions(&self) -> &Vec<QuestionAttempt> {
         &self.attempted_questions
     }
+
+    /// Get the attempts at questions that were scored automatically.
+    pub fn get_self_scored_questions(&self) -> Vec<&QuestionAttempt> {
+        self.attempted_questions
+            .iter()
+            .filter(|question_attempt| !question_attempt.is_instructor_scored())
+            .collect()
+    }
+
+    /// Get the attempts at questions that were scored by an instructor.
+    pub fn get_instructor_scored_questions(&self) -> Vec<&QuestionAttempt> {
+        self.attempted_questions
+            .iter()
+            .filter(|question_attempt| question_attempt.is_instructor_scored())
+            .collect()
+    }
 }
 
 pub const CIRCLE_IMAGE: &str =
@@ -516,3 +1433,280 @@ pub const HEXAGON_IMAGE: &str =
 
 pub const HEPTAGON_IMAGE: &str =
     "https://i.ibb.co/ZBPrtxm/360-F-315506920-w-RLWKFBTc-Vc0vprt9-Ckc0b-X5-Phs-LYf-OL.jpg";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A text question with some number of options, or none.
+    fn question(total_options: usize, answer: Answer) -> Question {
+        let options = (0..total_options)
+            .map(|index| QuestionOption::new(format!("option {}", index), QuestionOptionType::Text))
+            .collect::<Vec<_>>();
+        Question::new(
+            Prompt::new(PromptType::Text, "Which one?".to_string()),
+            if options.is_empty() {
+                None
+            } else {
+                Some(options)
+            },
+            None,
+            answer,
+            None,
+        )
+    }
+
+    fn is_invalid_question(result: Result<impl std::fmt::Debug>) -> bool {
+        matches!(result, Err(NeuroNudgeError::InvalidQuestion { .. }))
+    }
+
+    fn is_invalid_response(result: Result<ResponseEvaluation>) -> bool {
+        matches!(result, Err(NeuroNudgeError::InvalidResponse { .. }))
+    }
+
+    #[test]
+    fn selection_of_the_answer_is_correct() {
+        let question = question(3, Answer::Integer(1));
+
+        let evaluation = question.evaluate(&Response::Selection(1)).unwrap();
+        assert!(evaluation.is_correct());
+        assert_eq!(evaluation.get_credit(), 1.0);
+        assert!(!evaluation.is_instructor_scored());
+        let options = question.get_options().as_ref().unwrap();
+        assert_eq!(
+            evaluation.get_selected_option_id().as_ref(),
+            Some(options[1].get_id())
+        );
+
+        let evaluation = question.evaluate(&Response::Selection(2)).unwrap();
+        assert!(!evaluation.is_correct());
+        assert_eq!(evaluation.get_credit(), 0.0);
+        assert_eq!(
+            evaluation.get_selected_option_id().as_ref(),
+            Some(options[2].get_id())
+        );
+    }
+
+    #[test]
+    fn instructor_confirmation_is_instructor_scored() {
+        let question = question(0, Answer::Boolean(true));
+
+        let evaluation = question
+            .evaluate(&Response::InstructorConfirmation(true))
+            .unwrap();
+        assert!(evaluation.is_correct());
+        assert!(evaluation.is_instructor_scored());
+        assert_eq!(evaluation.get_selected_option_id(), &None);
+
+        let evaluation = question
+            .evaluate(&Response::InstructorConfirmation(false))
+            .unwrap();
+        assert!(!evaluation.is_correct());
+        assert!(evaluation.is_instructor_scored());
+    }
+
+    #[test]
+    fn selection_out_of_range_is_rejected() {
+        let question = question(3, Answer::Integer(0));
+        assert!(is_invalid_response(
+            question.evaluate(&Response::Selection(3))
+        ));
+    }
+
+    #[test]
+    fn response_of_the_wrong_kind_is_rejected() {
+        assert!(is_invalid_response(
+            question(3, Answer::Integer(0)).evaluate(&Response::InstructorConfirmation(true))
+        ));
+        assert!(is_invalid_response(
+            question(0, Answer::Boolean(true)).evaluate(&Response::Selection(0))
+        ));
+    }
+
+    #[test]
+    fn answer_out_of_range_is_invalid() {
+        let question = question(2, Answer::Integer(2));
+        assert!(is_invalid_question(question.validate()));
+        // A response to an invalid question can't be evaluated.
+        assert!(is_invalid_question(
+            question.evaluate(&Response::Selection(0))
+        ));
+    }
+
+    #[test]
+    fn instructor_confirmed_answer_with_options_is_invalid() {
+        assert!(is_invalid_question(
+            question(2, Answer::Boolean(true)).validate()
+        ));
+    }
+
+    #[test]
+    fn option_answer_without_options_is_invalid() {
+        assert!(is_invalid_question(
+            question(0, Answer::Integer(0)).validate()
+        ));
+    }
+
+    #[test]
+    fn multi_select_earns_partial_credit() {
+        let question = question(4, Answer::MultiSelect(vec![0, 2]));
+        let credit = |selected: Vec<u8>| {
+            question
+                .evaluate(&Response::Selections(selected))
+                .unwrap()
+                .get_credit()
+        };
+
+        assert_eq!(credit(vec![2, 0]), 1.0);
+        assert_eq!(credit(vec![0]), 0.5);
+        // Each incorrect selection takes away a correct one.
+        assert_eq!(credit(vec![0, 2, 1]), 0.5);
+        assert_eq!(credit(vec![0, 1]), 0.0);
+        assert_eq!(credit(vec![1, 3]), 0.0);
+        assert!(question
+            .evaluate(&Response::Selections(vec![0, 2]))
+            .unwrap()
+            .is_correct());
+        assert!(!question
+            .evaluate(&Response::Selections(vec![0]))
+            .unwrap()
+            .is_correct());
+    }
+
+    #[test]
+    fn ordering_earns_credit_for_each_option_in_place() {
+        let question = question(3, Answer::Ordering(vec![2, 0, 1]));
+
+        let evaluation = question.evaluate(&Response::Order(vec![2, 0, 1])).unwrap();
+        assert!(evaluation.is_correct());
+        assert_eq!(evaluation.get_credit(), 1.0);
+
+        let evaluation = question.evaluate(&Response::Order(vec![2, 1, 0])).unwrap();
+        assert!(!evaluation.is_correct());
+        assert!((evaluation.get_credit() - 1.0 / 3.0).abs() < 1e-6);
+
+        // Every option must be put in order.
+        assert!(is_invalid_response(
+            question.evaluate(&Response::Order(vec![2, 0]))
+        ));
+    }
+
+    #[test]
+    fn matching_pairs_match_either_way_round() {
+        let question = question(4, Answer::Matching(vec![(0, 2), (1, 3)]));
+
+        let evaluation = question
+            .evaluate(&Response::Matches(vec![(2, 0), (1, 3)]))
+            .unwrap();
+        assert!(evaluation.is_correct());
+        assert_eq!(evaluation.get_credit(), 1.0);
+
+        let evaluation = question.evaluate(&Response::Matches(vec![(3, 1)])).unwrap();
+        assert!(!evaluation.is_correct());
+        assert_eq!(evaluation.get_credit(), 0.5);
+
+        let evaluation = question
+            .evaluate(&Response::Matches(vec![(0, 1), (2, 3)]))
+            .unwrap();
+        assert_eq!(evaluation.get_credit(), 0.0);
+    }
+
+    #[test]
+    fn free_text_is_normalised_and_gets_full_credit_or_none() {
+        let question = question(
+            0,
+            Answer::FreeText(vec!["Circle".to_string(), "a circle".to_string()]),
+        );
+        let evaluate = |text: &str| {
+            question
+                .evaluate(&Response::Text(text.to_string()))
+                .unwrap()
+        };
+
+        assert_eq!(evaluate("  CIRCLE! ").get_credit(), 1.0);
+        assert!(evaluate("A   circle.").is_correct());
+        // A near answer is still wrong.
+        assert_eq!(evaluate("circles").get_credit(), 0.0);
+        assert!(!evaluate("a square").is_correct());
+    }
+
+    #[test]
+    fn responses_that_use_an_option_twice_are_rejected() {
+        assert!(is_invalid_response(
+            question(4, Answer::MultiSelect(vec![0, 2]))
+                .evaluate(&Response::Selections(vec![0, 0]))
+        ));
+        assert!(is_invalid_response(
+            question(3, Answer::Ordering(vec![2, 0, 1])).evaluate(&Response::Order(vec![2, 2, 1]))
+        ));
+        assert!(is_invalid_response(
+            question(4, Answer::Matching(vec![(0, 2), (1, 3)]))
+                .evaluate(&Response::Matches(vec![(0, 2), (2, 3)]))
+        ));
+    }
+
+    #[test]
+    fn responses_out_of_range_are_rejected() {
+        assert!(is_invalid_response(
+            question(4, Answer::MultiSelect(vec![0, 2]))
+                .evaluate(&Response::Selections(vec![0, 4]))
+        ));
+        assert!(is_invalid_response(
+            question(3, Answer::Ordering(vec![2, 0, 1])).evaluate(&Response::Order(vec![2, 0, 3]))
+        ));
+        assert!(is_invalid_response(
+            question(4, Answer::Matching(vec![(0, 2), (1, 3)]))
+                .evaluate(&Response::Matches(vec![(0, 9)]))
+        ));
+    }
+
+    #[test]
+    fn responses_of_another_answer_type_are_rejected() {
+        assert!(is_invalid_response(
+            question(4, Answer::MultiSelect(vec![0, 2])).evaluate(&Response::Order(vec![0, 2]))
+        ));
+        assert!(is_invalid_response(
+            question(3, Answer::Ordering(vec![2, 0, 1])).evaluate(&Response::Selection(2))
+        ));
+        assert!(is_invalid_response(
+            question(4, Answer::Matching(vec![(0, 2)])).evaluate(&Response::Selections(vec![0, 2]))
+        ));
+        assert!(is_invalid_response(
+            question(0, Answer::FreeText(vec!["circle".to_string()]))
+                .evaluate(&Response::InstructorConfirmation(true))
+        ));
+    }
+
+    #[test]
+    fn invalid_richer_answers_are_rejected() {
+        // Using an option twice.
+        assert!(is_invalid_question(
+            question(4, Answer::MultiSelect(vec![1, 1])).validate()
+        ));
+        assert!(is_invalid_question(
+            question(4, Answer::Matching(vec![(0, 1), (1, 2)])).validate()
+        ));
+        // Out of range.
+        assert!(is_invalid_question(
+            question(4, Answer::MultiSelect(vec![0, 4])).validate()
+        ));
+        // Picking out nothing.
+        assert!(is_invalid_question(
+            question(4, Answer::MultiSelect(vec![])).validate()
+        ));
+        assert!(is_invalid_question(
+            question(4, Answer::Matching(vec![])).validate()
+        ));
+        // An ordering that leaves out an option.
+        assert!(is_invalid_question(
+            question(3, Answer::Ordering(vec![0, 1])).validate()
+        ));
+        // Free text with options, or with nothing to accept.
+        assert!(is_invalid_question(
+            question(2, Answer::FreeText(vec!["circle".to_string()])).validate()
+        ));
+        assert!(is_invalid_question(
+            question(0, Answer::FreeText(vec![" ! ".to_string()])).validate()
+        ));
+    }
+}
@@ -0,0 +1,792 @@
+//! This defines the content file format, so that curricula can be written without
+//! touching any Rust.
+//!
+//! A content file holds one or more modules, each with its lessons, and each lesson
+//! with its questions. A question lists its prompt, options, answer, hints, its own
+//! difficulty level if it differs from its lesson's, and the ASD traits that a learner
+//! needs for the best chance of success. Content files can be TOML, JSON or YAML, e.g.
+//!
+//! ```toml
+//! [[modules]]
+//! name = "Shapes"
+//!
+//! [[modules.lessons]]
+//! name = "Recognising Circles"
+//! difficulty_level = "VeryEasy"
+//!
+//! [[modules.lessons.questions]]
+//! prompt = { text = "Select the circle!" }
+//! options = [{ image = "https://example.com/circle.jpg" }, { image = "https://example.com/square.jpg" }]
+//! answer = { integer = 0 }
+//! hints = ["Count the sides of each shape."]
+//! asd_traits = { attention_span = 1, communicability = ["NonVerbal"], communication_level = "Low", motor_skills = "Low" }
+//! ```
+//!
+//! The answer is one of `integer` (the index of the correct option), `boolean` (what
+//! the instructor should confirm), `multi_select`, `ordering`, `matching` (pairs of
+//! option indices) or `free_text` (the accepted answers). A prompt is one of `text`,
+//! `image` or `video`, where a video also needs its `instruction`.
+//!
+//! A module can give the `version` of its content, which starts at 1 and is bumped
+//! whenever its lessons change (see `ContentMigration`). Modules and lessons can be
+//! given an `id`. Otherwise, like every question and option, their ids are derived
+//! from their content (see `Lesson::new` and `Lesson::with_derived_question_ids`), so
+//! the same file always loads with the same ids and saved learner progress still
+//! matches it.
+//!
+//! Lessons can list the names of the lessons in their module that are their
+//! `prerequisites`, and modules the names of the modules in the file that are theirs.
+//! Prerequisites that form a cycle are rejected when the file is loaded.
+//!
+//! Every question is validated as it is loaded. Any error in a file - malformed syntax,
+//! an unknown field, or an answer that doesn't make sense with its options - names the
+//! field it was found at, e.g. `modules[0].lessons[2].questions[1].answer`, and the
+//! line if the format gives one.
+//!
+
+use std::path::Path;
+
+use serde::de::value::MapAccessDeserializer;
+use serde::de::{Error as _, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+
+use crate::content::{
+    Answer, ContentModule, DifficultyLevel, Lesson, Prompt, PromptType, Question, QuestionOption,
+    QuestionOptionType, FIRST_CONTENT_VERSION,
+};
+use crate::error::{NeuroNudgeError, Result};
+use crate::learner::{ASDTraits, Communicability, CommunicationLevel, MotorSkills};
+use crate::prerequisites::PrerequisiteGraph;
+
+/// ContentFile
+/// The modules of a content file, as they are written.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContentFile {
+    #[serde(deserialize_with = "non_empty")]
+    modules: Vec<ModuleEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ModuleEntry {
+    id: Option<String>,
+    name: String,
+    version: Option<u32>,
+    /// The names of the modules in the file that must be mastered first.
+    #[serde(default)]
+    prerequisites: Vec<String>,
+    #[serde(deserialize_with = "non_empty")]
+    lessons: Vec<LessonEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LessonEntry {
+    id: Option<String>,
+    name: String,
+    difficulty_level: DifficultyLevel,
+    /// The names of the lessons in the module that must be mastered first.
+    #[serde(default)]
+    prerequisites: Vec<String>,
+    #[serde(deserialize_with = "non_empty")]
+    questions: Vec<QuestionEntry>,
+}
+
+/// A question, validated as soon as it is read so that any error in it is reported at
+/// the question's line.
+#[derive(Debug, Clone)]
+struct QuestionEntry(Question);
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct QuestionFields {
+    prompt: PromptEntry,
+    #[serde(default)]
+    options: Vec<OptionEntry>,
+    answer: AnswerEntry,
+    #[serde(default)]
+    hints: Vec<String>,
+    asd_traits: Option<AsdTraitsEntry>,
+    difficulty_level: Option<DifficultyLevel>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum PromptEntry {
+    Text(String),
+    Image(String),
+    Video { url: String, instruction: String },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum AnswerEntry {
+    Integer(u8),
+    Boolean(bool),
+    MultiSelect(Vec<u8>),
+    Ordering(Vec<u8>),
+    Matching(Vec<(u8, u8)>),
+    FreeText(Vec<String>),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum OptionEntry {
+    Text(String),
+    Image(String),
+    Video(String),
+    Audio(String),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AsdTraitsEntry {
+    /// In minutes. Learners' attention spans are compared to it as a ratio, so it must
+    /// be at least 1.
+    #[serde(deserialize_with = "positive")]
+    attention_span: i32,
+    /// Learners' communicability is compared to it as a share, so it can't be empty.
+    #[serde(deserialize_with = "non_empty")]
+    communicability: Vec<Communicability>,
+    communication_level: CommunicationLevel,
+    motor_skills: MotorSkills,
+}
+
+/// The content file format, which is usually known from the file's extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentFormat {
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl ContentFormat {
+    /// Get the format of a file from its extension.
+    pub fn from_path(path: &Path) -> Result<ContentFormat> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Ok(ContentFormat::Toml),
+            Some("json") => Ok(ContentFormat::Json),
+            Some("yaml") | Some("yml") => Ok(ContentFormat::Yaml),
+            _ => Err(NeuroNudgeError::Serialization(format!(
+                "{} is neither a .toml, .json nor .yaml file",
+                path.display()
+            ))),
+        }
+    }
+}
+
+fn non_empty<'de, D, T>(deserializer: D) -> std::result::Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let entries = Vec::<T>::deserialize(deserializer)?;
+    if entries.is_empty() {
+        return Err(D::Error::custom("there must be at least one entry"));
+    }
+    Ok(entries)
+}
+
+fn positive<'de, D>(deserializer: D) -> std::result::Result<i32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = i32::deserialize(deserializer)?;
+    if value < 1 {
+        return Err(D::Error::custom(format!(
+            "must be at least 1, but is {}",
+            value
+        )));
+    }
+    Ok(value)
+}
+
+/// The line that a byte offset into some text is on, counting from 1.
+fn line_at(contents: &str, offset: usize) -> usize {
+    contents[..offset.min(contents.len())].matches('\n').count() + 1
+}
+
+/// Build the error for a content file that couldn't be parsed. The JSON and YAML
+/// parsers add where the error was to its message, as " at line 4 column 3", which is
+/// cut off as the error already has a field and line of its own.
+fn invalid_content<E: std::fmt::Display>(
+    error: &serde_path_to_error::Error<E>,
+    line_and_column: Option<(usize, usize)>,
+) -> NeuroNudgeError {
+    let message = error.inner().to_string();
+    let reason = match line_and_column {
+        Some((line, column)) => message
+            .strip_suffix(&format!(" at line {} column {}", line, column))
+            .unwrap_or(&message),
+        None => &message,
+    };
+    NeuroNudgeError::InvalidContent {
+        field: error.path().to_string(),
+        line: line_and_column.map(|(line, _)| line),
+        reason: reason.to_string(),
+    }
+}
+
+/// Lets YAML content files write each enum, e.g. an answer, as a map with a single key,
+/// the same as TOML and JSON content files do, rather than with a YAML tag.
+struct SingletonMaps<T>(T);
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for SingletonMaps<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        serde_yaml::with::singleton_map_recursive::deserialize(deserializer).map(SingletonMaps)
+    }
+}
+
+impl<'de> Deserialize<'de> for QuestionEntry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_map(QuestionVisitor)
+    }
+}
+
+/// Converts a question's fields while the deserializer is still within the question,
+/// so the error of a question that doesn't validate is located at the question itself.
+/// Converting them afterwards, as `#[serde(try_from)]` does, would locate it at the
+/// list of questions instead.
+struct QuestionVisitor;
+
+impl<'de> Visitor<'de> for QuestionVisitor {
+    type Value = QuestionEntry;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a question")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> std::result::Result<QuestionEntry, A::Error> {
+        let fields = QuestionFields::deserialize(MapAccessDeserializer::new(map))?;
+        QuestionEntry::try_from(fields).map_err(A::Error::custom)
+    }
+}
+
+impl TryFrom<QuestionFields> for QuestionEntry {
+    type Error = String;
+
+    fn try_from(fields: QuestionFields) -> std::result::Result<QuestionEntry, String> {
+        let prompt = match fields.prompt {
+            PromptEntry::Text(text) => Prompt::new(PromptType::Text, text),
+            PromptEntry::Image(url) => Prompt::new(PromptType::Image, url),
+            PromptEntry::Video { url, instruction } => {
+                Prompt::new(PromptType::Video(instruction), url)
+            }
+        };
+        let options: Vec<QuestionOption> = fields
+            .options
+            .into_iter()
+            .map(|option| match option {
+                OptionEntry::Text(text) => QuestionOption::new(text, QuestionOptionType::Text),
+                OptionEntry::Image(url) => QuestionOption::new(url, QuestionOptionType::Image),
+                OptionEntry::Video(url) => QuestionOption::new(url, QuestionOptionType::Video),
+                OptionEntry::Audio(url) => QuestionOption::new(url, QuestionOptionType::Audio),
+            })
+            .collect();
+        let answer = match fields.answer {
+            AnswerEntry::Integer(index) => Answer::Integer(index),
+            AnswerEntry::Boolean(confirmation) => Answer::Boolean(confirmation),
+            AnswerEntry::MultiSelect(indices) => Answer::MultiSelect(indices),
+            AnswerEntry::Ordering(indices) => Answer::Ordering(indices),
+            AnswerEntry::Matching(pairs) => Answer::Matching(pairs),
+            AnswerEntry::FreeText(accepted) => Answer::FreeText(accepted),
+        };
+        let asd_traits = fields.asd_traits.map(|asd_traits| {
+            ASDTraits::new(
+                "".to_string(),
+                asd_traits.attention_span,
+                asd_traits.communicability,
+                asd_traits.communication_level,
+                asd_traits.motor_skills,
+            )
+        });
+
+        let mut question = Question::new(
+            prompt,
+            (!options.is_empty()).then_some(options),
+            (!fields.hints.is_empty()).then_some(fields.hints),
+            answer,
+            asd_traits,
+        );
+        if let Some(difficulty_level) = fields.difficulty_level {
+            question = question.with_difficulty_level(difficulty_level);
+        }
+        // The question's id is generated, so it isn't any help to the content's author.
+        question.validate().map_err(|error| match error {
+            NeuroNudgeError::InvalidQuestion { reason, .. } => reason,
+            error => error.to_string(),
+        })?;
+        Ok(QuestionEntry(question))
+    }
+}
+
+impl ContentFile {
+    /// Parse a content file from TOML.
+    pub fn from_toml_str(toml: &str) -> Result<ContentFile> {
+        let deserializer = toml::Deserializer::new(toml);
+        serde_path_to_error::deserialize(deserializer).map_err(|error| {
+            NeuroNudgeError::InvalidContent {
+                field: error.path().to_string(),
+                line: error.inner().span().map(|span| line_at(toml, span.start)),
+                reason: error.inner().message().to_string(),
+            }
+        })
+    }
+
+    /// Parse a content file from JSON.
+    pub fn from_json_str(json: &str) -> Result<ContentFile> {
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        serde_path_to_error::deserialize(&mut deserializer).map_err(|error| {
+            let line_and_column =
+                Some((error.inner().line(), error.inner().column())).filter(|&(line, _)| line > 0);
+            invalid_content(&error, line_and_column)
+        })
+    }
+
+    /// Parse a content file from YAML.
+    pub fn from_yaml_str(yaml: &str) -> Result<ContentFile> {
+        let deserializer = serde_yaml::Deserializer::from_str(yaml);
+        serde_path_to_error::deserialize(deserializer)
+            .map(|SingletonMaps(content_file)| content_file)
+            .map_err(|error| {
+                let line_and_column = error
+                    .inner()
+                    .location()
+                    .map(|location| (location.line(), location.column()));
+                let mut error = invalid_content(&error, line_and_column);
+                // YAML errors also start with the path of the value that they're about.
+                if let NeuroNudgeError::InvalidContent { reason, .. } = &mut error {
+                    if let Some((_, message)) = reason
+                        .split_once(": ")
+                        .filter(|(path, _)| !path.contains(' '))
+                    {
+                        *reason = message.to_string();
+                    }
+                }
+                error
+            })
+    }
+
+    /// Parse a content file in the given format.
+    pub fn parse(contents: &str, format: ContentFormat) -> Result<ContentFile> {
+        match format {
+            ContentFormat::Toml => ContentFile::from_toml_str(contents),
+            ContentFormat::Json => ContentFile::from_json_str(contents),
+            ContentFormat::Yaml => ContentFile::from_yaml_str(contents),
+        }
+    }
+
+    /// Load a content file from a `.toml`, `.json` or `.yaml` file.
+    pub fn load(path: impl AsRef<Path>) -> Result<ContentFile> {
+        let path = path.as_ref();
+        let format = ContentFormat::from_path(path)?;
+        let contents = std::fs::read_to_string(path)?;
+        ContentFile::parse(&contents, format)
+    }
+
+    /// Build the file's modules, with their lessons in the order they were written.
+    /// Fails if two modules, or two lessons of a module, have the same id, e.g. lessons
+    /// with the same name and difficulty level. Also fails if a prerequisite isn't the
+    /// name of a lesson in the same module, or of a module in the file, or if the
+    /// prerequisites form a cycle.
+    pub fn into_modules(self) -> Result<Vec<ContentModule>> {
+        let module_names: Vec<String> = self.modules.iter().map(|m| m.name.clone()).collect();
+        let mut modules = vec![];
+        for (module_index, module) in self.modules.into_iter().enumerate() {
+            let field = format!("modules[{}]", module_index);
+            let module_prerequisites = resolve_prerequisites(
+                &module.prerequisites,
+                &module_names,
+                &module_names,
+                &format!("{}.prerequisites", field),
+                "module",
+            )?;
+
+            let mut content_module = match module.id {
+                Some(id) => ContentModule::new_with_id(id, module.name),
+                None => ContentModule::new(module.name),
+            }
+            .with_version(module.version.unwrap_or(FIRST_CONTENT_VERSION))
+            .with_prerequisites(module_prerequisites);
+
+            let lesson_names: Vec<String> = module.lessons.iter().map(|l| l.name.clone()).collect();
+            let mut lessons = vec![];
+            for lesson in module.lessons {
+                let questions = lesson.questions.into_iter().map(|q| q.0).collect();
+                let module_id = content_module.get_id().clone();
+                let built_lesson = match lesson.id {
+                    Some(id) => Lesson::new_with_id(
+                        id,
+                        lesson.name,
+                        questions,
+                        lesson.difficulty_level,
+                        module_id,
+                    )
+                    .with_derived_question_ids(),
+                    None => Lesson::new(lesson.name, questions, lesson.difficulty_level, module_id),
+                };
+                lessons.push((built_lesson, lesson.prerequisites));
+            }
+
+            let lesson_ids: Vec<String> = lessons.iter().map(|(l, _)| l.get_id().clone()).collect();
+            check_unique_ids(&lesson_ids, &format!("{}.lessons", field), "lesson")?;
+            for (lesson_index, (lesson, prerequisites)) in lessons.into_iter().enumerate() {
+                let lesson_prerequisites = resolve_prerequisites(
+                    &prerequisites,
+                    &lesson_ids,
+                    &lesson_names,
+                    &format!("{}.lessons[{}].prerequisites", field, lesson_index),
+                    "lesson",
+                )?;
+                content_module.add_lesson(lesson.with_prerequisites(lesson_prerequisites));
+            }
+
+            let graph = PrerequisiteGraph::for_lessons(content_module.get_lessons());
+            check_acyclic(
+                &graph,
+                &lesson_ids,
+                &lesson_names,
+                &format!("{}.lessons", field),
+            )?;
+            modules.push(content_module);
+        }
+
+        let module_ids: Vec<String> = modules.iter().map(|m| m.get_id().clone()).collect();
+        check_unique_ids(&module_ids, "modules", "module")?;
+        let graph = PrerequisiteGraph::for_modules(&modules);
+        check_acyclic(&graph, &module_ids, &module_names, "modules")?;
+        Ok(modules)
+    }
+}
+
+/// Check that no two lessons or modules have the same id, as one would silently
+/// replace the other wherever they're looked up by id.
+fn check_unique_ids(ids: &[String], field: &str, kind: &str) -> Result<()> {
+    for (index, id) in ids.iter().enumerate() {
+        if let Some(earlier_index) = ids[..index].iter().position(|earlier| earlier == id) {
+            return Err(NeuroNudgeError::InvalidContent {
+                field: format!("{}[{}]", field, index),
+                line: None,
+                reason: format!(
+                    "the {} has the same id as {}[{}], {}",
+                    kind, field, earlier_index, id
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Resolve the names of some prerequisites into the ids of the lessons or modules that
+/// have them, given the ids in the same order as their names.
+fn resolve_prerequisites(
+    prerequisites: &[String],
+    ids: &[String],
+    names: &[String],
+    field: &str,
+    kind: &str,
+) -> Result<Vec<String>> {
+    prerequisites
+        .iter()
+        .map(|prerequisite| {
+            let mut matches = names
+                .iter()
+                .enumerate()
+                .filter(|(_, name)| *name == prerequisite);
+            match (matches.next(), matches.next()) {
+                (Some((index, _)), None) => Ok(ids[index].clone()),
+                (Some(_), Some(_)) => Err(format!(
+                    "more than one {} is named \"{}\"",
+                    kind, prerequisite
+                )),
+                (None, _) => Err(format!("there is no {} named \"{}\"", kind, prerequisite)),
+            }
+            .map_err(|reason| NeuroNudgeError::InvalidContent {
+                field: field.to_string(),
+                line: None,
+                reason,
+            })
+        })
+        .collect()
+}
+
+/// Check that some prerequisites don't form a cycle, naming the lessons or modules
+/// along any cycle found.
+fn check_acyclic(
+    graph: &PrerequisiteGraph,
+    ids: &[String],
+    names: &[String],
+    field: &str,
+) -> Result<()> {
+    let Some(cycle) = graph.find_cycle() else {
+        return Ok(());
+    };
+    let cycle_names: Vec<&str> = cycle
+        .iter()
+        .map(|id| {
+            ids.iter()
+                .position(|i| i == id)
+                .map_or(id.as_str(), |index| names[index].as_str())
+        })
+        .collect();
+    Err(NeuroNudgeError::InvalidContent {
+        field: field.to_string(),
+        line: None,
+        reason: format!("prerequisites form a cycle: {}", cycle_names.join(" -> ")),
+    })
+}
+
+/// Load the modules of a `.toml`, `.json` or `.yaml` content file.
+pub fn load_content_modules(path: impl AsRef<Path>) -> Result<Vec<ContentModule>> {
+    ContentFile::load(path)?.into_modules()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML: &str = r#"[[modules]]
+name = "Shapes"
+
+[[modules.lessons]]
+name = "Recognising Circles"
+difficulty_level = "VeryEasy"
+
+[[modules.lessons.questions]]
+prompt = { text = "Select the circle!" }
+options = [{ image = "circle.jpg" }, { image = "square.jpg" }]
+answer = { integer = 0 }
+asd_traits = { attention_span = 1, communicability = ["NonVerbal"], communication_level = "Low", motor_skills = "Low" }
+
+[[modules.lessons.questions]]
+prompt = { text = "Select the square!" }
+options = [{ image = "circle.jpg" }, { image = "square.jpg" }]
+answer = { integer = 1 }
+"#;
+
+    const JSON: &str = r#"{
+  "modules": [
+    {
+      "name": "Shapes",
+      "lessons": [
+        {
+          "name": "Recognising Circles",
+          "difficulty_level": "VeryEasy",
+          "questions": [
+            {
+              "prompt": { "text": "Select the circle!" },
+              "options": [{ "image": "circle.jpg" }, { "image": "square.jpg" }],
+              "answer": { "integer": 0 },
+              "asd_traits": { "attention_span": 1, "communicability": ["NonVerbal"], "communication_level": "Low", "motor_skills": "Low" }
+            },
+            {
+              "prompt": { "text": "Select the square!" },
+              "options": [{ "image": "circle.jpg" }, { "image": "square.jpg" }],
+              "answer": { "integer": 1 }
+            }
+          ]
+        }
+      ]
+    }
+  ]
+}
+"#;
+
+    const YAML: &str = r#"modules:
+  - name: Shapes
+    lessons:
+      - name: Recognising Circles
+        difficulty_level: VeryEasy
+        questions:
+          - prompt: { text: Select the circle! }
+            options: [{ image: circle.jpg }, { image: square.jpg }]
+            answer: { integer: 0 }
+            asd_traits: { attention_span: 1, communicability: [NonVerbal], communication_level: Low, motor_skills: Low }
+          - prompt: { text: Select the square! }
+            options: [{ image: circle.jpg }, { image: square.jpg }]
+            answer: { integer: 1 }
+"#;
+
+    /// The example file in each format, with an edit made to each.
+    fn edited_files(edits: [(&str, &str); 3]) -> [(ContentFormat, String); 3] {
+        let edit = |contents: &str, (from, to): (&str, &str)| {
+            assert!(contents.contains(from), "{} isn't in the file", from);
+            contents.replacen(from, to, 1)
+        };
+        [
+            (ContentFormat::Toml, edit(TOML, edits[0])),
+            (ContentFormat::Json, edit(JSON, edits[1])),
+            (ContentFormat::Yaml, edit(YAML, edits[2])),
+        ]
+    }
+
+    fn load(contents: &str, format: ContentFormat) -> Result<Vec<ContentModule>> {
+        ContentFile::parse(contents, format)?.into_modules()
+    }
+
+    /// The field, line and reason of the error in some content.
+    fn content_error(contents: &str, format: ContentFormat) -> (String, Option<usize>, String) {
+        match load(contents, format) {
+            Err(NeuroNudgeError::InvalidContent {
+                field,
+                line,
+                reason,
+            }) => (field, line, reason),
+            result => panic!("expected invalid {:?} content, got {:?}", format, result),
+        }
+    }
+
+    #[test]
+    fn every_format_loads_the_same_content() {
+        let modules = [
+            load(TOML, ContentFormat::Toml).unwrap(),
+            load(JSON, ContentFormat::Json).unwrap(),
+            load(YAML, ContentFormat::Yaml).unwrap(),
+        ];
+
+        let lessons = modules[0][0].get_lessons();
+        assert_eq!(lessons.len(), 1);
+        assert_eq!(lessons[0].get_questions().len(), 2);
+        // The ids are derived from the content, so they match across formats.
+        assert_eq!(modules[0], modules[1]);
+        assert_eq!(modules[0], modules[2]);
+    }
+
+    #[test]
+    fn invalid_question_is_reported_at_the_question() {
+        let files = edited_files([
+            ("answer = { integer = 1 }", "answer = { integer = 2 }"),
+            (
+                r#""answer": { "integer": 1 }"#,
+                r#""answer": { "integer": 2 }"#,
+            ),
+            ("answer: { integer: 1 }", "answer: { integer: 2 }"),
+        ]);
+        // JSON errors are at the end of the question, the others at its start.
+        let lines = [14, 20, 11];
+
+        for ((format, contents), line) in files.iter().zip(lines) {
+            let (field, error_line, reason) = content_error(contents, *format);
+            assert_eq!(field, "modules[0].lessons[0].questions[1]", "{:?}", format);
+            assert_eq!(error_line, Some(line), "{:?}", format);
+            assert_eq!(reason, "answer index 2 is out of range of its 2 options");
+        }
+    }
+
+    #[test]
+    fn unknown_field_is_rejected() {
+        let files = edited_files([
+            (
+                "answer = { integer = 1 }",
+                "answer = { integer = 1 }\nhint = \"Look!\"",
+            ),
+            (
+                r#""answer": { "integer": 1 }"#,
+                r#""answer": { "integer": 1 }, "hint": "Look!""#,
+            ),
+            (
+                "answer: { integer: 1 }",
+                "answer: { integer: 1 }\n            hint: Look!",
+            ),
+        ]);
+        let lines = [18, 19, 14];
+
+        for ((format, contents), line) in files.iter().zip(lines) {
+            let (field, error_line, reason) = content_error(contents, *format);
+            assert_eq!(
+                field, "modules[0].lessons[0].questions[1].hint",
+                "{:?}",
+                format
+            );
+            assert_eq!(error_line, Some(line), "{:?}", format);
+            assert!(reason.starts_with("unknown field `hint`"), "{}", reason);
+        }
+    }
+
+    #[test]
+    fn unknown_answer_kind_is_rejected() {
+        let files = edited_files([
+            ("answer = { integer = 1 }", "answer = { index = 1 }"),
+            (
+                r#""answer": { "integer": 1 }"#,
+                r#""answer": { "index": 1 }"#,
+            ),
+            ("answer: { integer: 1 }", "answer: { index: 1 }"),
+        ]);
+        let lines = [17, 19, 13];
+
+        for ((format, contents), line) in files.iter().zip(lines) {
+            let (field, error_line, reason) = content_error(contents, *format);
+            // YAML answers are read as a map with a single key, which is part of the
+            // field.
+            assert!(
+                field.starts_with("modules[0].lessons[0].questions[1].answer"),
+                "{:?}: {}",
+                format,
+                field
+            );
+            assert_eq!(error_line, Some(line), "{:?}", format);
+            assert!(reason.starts_with("unknown variant `index`"), "{}", reason);
+        }
+    }
+
+    #[test]
+    fn attention_span_must_be_positive() {
+        for attention_span in ["0", "-2"] {
+            let files = edited_files([
+                (
+                    "attention_span = 1",
+                    &format!("attention_span = {}", attention_span),
+                ),
+                (
+                    r#""attention_span": 1"#,
+                    &format!(r#""attention_span": {}"#, attention_span),
+                ),
+                (
+                    "attention_span: 1",
+                    &format!("attention_span: {}", attention_span),
+                ),
+            ]);
+            let lines = [12, 14, 10];
+
+            for ((format, contents), line) in files.iter().zip(lines) {
+                let (field, error_line, reason) = content_error(contents, *format);
+                assert_eq!(
+                    field,
+                    "modules[0].lessons[0].questions[0].asd_traits.attention_span"
+                );
+                assert_eq!(error_line, Some(line), "{:?}", format);
+                assert_eq!(
+                    reason,
+                    format!("must be at least 1, but is {}", attention_span)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unresolved_prerequisite_is_rejected() {
+        let files = edited_files([
+            (
+                "difficulty_level = \"VeryEasy\"",
+                "difficulty_level = \"VeryEasy\"\nprerequisites = [\"Counting\"]",
+            ),
+            (
+                r#""difficulty_level": "VeryEasy","#,
+                r#""difficulty_level": "VeryEasy", "prerequisites": ["Counting"],"#,
+            ),
+            (
+                "difficulty_level: VeryEasy",
+                "difficulty_level: VeryEasy\n        prerequisites: [Counting]",
+            ),
+        ]);
+
+        for (format, contents) in &files {
+            let (field, line, reason) = content_error(contents, *format);
+            assert_eq!(field, "modules[0].lessons[0].prerequisites");
+            assert_eq!(line, None);
+            assert_eq!(reason, "there is no lesson named \"Counting\"");
+        }
+    }
+}
@@ -12,20 +12,30 @@
 //!   reflect that with a reward that is positive, vice versa.
 //!
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use rand::Rng;
 
-use crate::content::{DifficultyLevel, Lesson, LessonResult};
-
-// Define mastery thresholds as constants
-const BASIC_MASTERY_THRESHOLD: f32 = 0.5;
-const COMPETENT_MASTERY_THRESHOLD: f32 = 0.7;
-const FULL_MASTERY_THRESHOLD: f32 = 0.8;
-
-pub type QTable = HashMap<(Lesson, DifficultyLevel), f32>;
-
-#[derive(Debug, Clone)]
+use crate::config::EngineConfig;
+use crate::content::{
+    ContentMigration, DifficultyLevel, LessonCatalogue, LessonId, LessonResult, PromptLevel,
+    QuestionAttempt, DIFFICULTY_LEVELS, FIRST_CONTENT_VERSION,
+};
+use crate::error::{NeuroNudgeError, Result};
+use crate::prerequisites::PrerequisiteGraph;
+
+/// A state in the q table - some lesson (by its id) at its difficulty level.
+/// The lessons themselves are kept separately in a `LessonCatalogue`, so the q table
+/// never has to hash or clone whole lessons.
+pub type LessonState = (LessonId, DifficultyLevel);
+
+pub type QTable = HashMap<LessonState, f32>;
+
+/// Mastery
+/// How well a learner has mastered some lesson or difficulty level, ordered from None
+/// to Full.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Mastery {
     None,
     Basic,
@@ -37,25 +47,60 @@ pub enum Mastery {
 /// As per comment blob at top of the file. This struct specifically deals with
 /// a single q table associated to some module under some learner.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct QTableAlgorithm {
     id: String,
     /// The QTable is a mapping between a state and an action, and the value
     /// of that action.
+    #[cfg_attr(feature = "serde", serde(with = "q_table_serde"))]
     q_table: QTable,
     epsilon: f32,
-    discount_factor: f32,
-    learning_rate: f32,
     strategy: Strategy,
+    /// The hyperparameters - learning rate, discount factor, mastery thresholds,
+    /// reward weights and decay settings.
+    config: EngineConfig,
     decay_counters: HashMap<DifficultyLevel, f32>,
     /// Keeps track of how many attempts have passed since a particular difficulty
     /// level was attempted
     total_difficulty_non_attempts: HashMap<DifficultyLevel, f32>,
     has_attempted_difficulty: HashMap<DifficultyLevel, bool>,
     consecutive_attempts: HashMap<DifficultyLevel, f32>,
+    /// How a lesson is chosen when the engine moves to a difficulty level that has
+    /// more than one lesson in it.
+    lesson_selection: LessonSelection,
+    /// The update count at which each lesson was last practised, so that lessons
+    /// at the same difficulty level can be rotated through.
+    lesson_last_practised: HashMap<LessonId, u64>,
+    total_updates: u64,
+    /// The prompt level recommended for the next attempt at each lesson, for lessons
+    /// whose results have recorded prompt levels.
+    recommended_prompt_levels: HashMap<LessonId, PromptLevel>,
+    /// The version of the module's content that the q table's lessons are from.
+    content_version: u32,
+    /// The prerequisites of each lesson that has any. A lesson is only recommended once
+    /// all of its prerequisites reach the configured `prerequisite_mastery`.
+    lesson_prerequisites: HashMap<LessonId, Vec<LessonId>>,
+}
+
+/// LessonSelection
+/// A module can have several lessons for the same difficulty level. When the engine
+/// decides to move to (or stay at) some difficulty level, this determines which of
+/// the lessons at that level is chosen.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LessonSelection {
+    /// Choose the lesson with the highest q value.
+    BestValue,
+    /// Choose the lesson that was practised least recently, so that the learner
+    /// rotates through all lessons at a level. Lessons never practised come first.
+    LeastRecentlyPractised,
+    /// Choose a random lesson.
+    Exploration,
 }
 
 /// Strategy used by the engine
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Strategy {
     BaseQLearning,
     MasteryThresholds,
@@ -64,70 +109,266 @@ pub enum Strategy {
 }
 
 impl QTableAlgorithm {
-    pub fn new(q_table: Option<QTable>, epsilon: f32, strategy: Strategy) -> QTableAlgorithm {
+    /// Create an algorithm with some config, which fails if the config can't be used
+    /// by the engine, see `EngineConfig::validate`.
+    pub fn new(
+        q_table: Option<QTable>,
+        epsilon: f32,
+        strategy: Strategy,
+        config: EngineConfig,
+    ) -> Result<QTableAlgorithm> {
+        config.validate()?;
+
         let mut decay_counters = HashMap::new();
         let mut total_difficulty_non_attempts = HashMap::new();
         let mut consecutive_attempts = HashMap::new();
 
-        let difficulties = [
-            DifficultyLevel::VeryEasy,
-            DifficultyLevel::Easy,
-            DifficultyLevel::Medium,
-            DifficultyLevel::Hard,
-            DifficultyLevel::VeryHard,
-            DifficultyLevel::Expert,
-            DifficultyLevel::Master,
-            DifficultyLevel::Grandmaster,
-        ];
-
-        for difficulty in &difficulties {
-            let total_decays_expected = match difficulty {
-                DifficultyLevel::VeryEasy => 2.0,
-                DifficultyLevel::Easy => 3.0,
-                DifficultyLevel::Medium => 4.0,
-                DifficultyLevel::Hard => 5.0,
-                DifficultyLevel::VeryHard => 6.0,
-                DifficultyLevel::Expert => 7.0,
-                DifficultyLevel::Master => 8.0,
-                DifficultyLevel::Grandmaster => 9.0,
-            };
+        for difficulty in &DIFFICULTY_LEVELS {
+            let total_decays_expected = *config.decay_counters.get(difficulty);
 
             decay_counters.insert(difficulty.clone(), total_decays_expected);
             total_difficulty_non_attempts.insert(difficulty.clone(), 0.0);
             consecutive_attempts.insert(difficulty.clone(), 0.0);
         }
 
-        QTableAlgorithm {
+        Ok(QTableAlgorithm {
             id: uuid::Uuid::new_v4().to_string(),
-            q_table: q_table.unwrap_or(HashMap::new()),
-            discount_factor: 0.25,
-            learning_rate: 0.75,
+            q_table: q_table.unwrap_or_default(),
             epsilon,
             strategy,
+            config,
             decay_counters,
             total_difficulty_non_attempts,
             has_attempted_difficulty: HashMap::new(),
             consecutive_attempts,
+            lesson_selection: LessonSelection::LeastRecentlyPractised,
+            lesson_last_practised: HashMap::new(),
+            total_updates: 0,
+            recommended_prompt_levels: HashMap::new(),
+            content_version: FIRST_CONTENT_VERSION,
+            lesson_prerequisites: HashMap::new(),
+        })
+    }
+
+    /// Take the prerequisites of the module's lessons, so that a lesson is only
+    /// recommended once its prerequisites are mastered. Fails if a prerequisite isn't
+    /// one of the lessons, or if the prerequisites form a cycle.
+    pub fn set_lesson_prerequisites(&mut self, lessons: &LessonCatalogue) -> Result<()> {
+        self.lesson_prerequisites = lesson_prerequisites(lessons)?;
+        Ok(())
+    }
+
+    /// Get the mastery of a lesson, as per the mastery thresholds applied to its q
+    /// value, or None if it isn't in the q table.
+    pub fn get_mastery_for_lesson(&self, lesson_id: &str) -> Mastery {
+        self.q_table
+            .iter()
+            .filter(|((l, _), _)| l == lesson_id)
+            .map(|(_, &v)| v)
+            .max_by(|x, y| x.partial_cmp(y).unwrap_or(std::cmp::Ordering::Equal))
+            .map_or(Mastery::None, |value| self.mastery_for_value(value))
+    }
+
+    /// Get the mastery of the whole module, which is the mastery of the hardest
+    /// difficulty level that has lessons, or None if the q table has no lessons.
+    pub fn get_module_mastery(&self) -> Mastery {
+        DIFFICULTY_LEVELS
+            .iter()
+            .rev()
+            .find(|difficulty_level| self.q_table.keys().any(|(_, d)| d == *difficulty_level))
+            .map_or(Mastery::None, |difficulty_level| {
+                self.get_mastery_for_difficulty(difficulty_level)
+            })
+    }
+
+    /// Check if all of a lesson's prerequisites reach the configured mastery.
+    pub fn is_lesson_unlocked(&self, lesson_id: &str) -> bool {
+        self.lesson_prerequisites
+            .get(lesson_id)
+            .into_iter()
+            .flatten()
+            .all(|prerequisite| {
+                self.get_mastery_for_lesson(prerequisite) >= self.config.prerequisite_mastery
+            })
+    }
+
+    /// Check that the lessons' prerequisites don't form a cycle, e.g. after they were
+    /// restored from a snapshot rather than set from the lessons.
+    pub fn check_lesson_prerequisites(&self) -> Result<()> {
+        let mut graph = PrerequisiteGraph::new();
+        for (lesson_id, prerequisites) in &self.lesson_prerequisites {
+            graph.add(lesson_id, prerequisites);
+        }
+        graph.check_acyclic()
+    }
+
+    /// Find the first prerequisite of a locked lesson that still needs to be mastered
+    /// and can be recommended now, looking through the prerequisites of prerequisites
+    /// that are locked themselves. Lessons already looked through are skipped, so this
+    /// ends even if the prerequisites form a cycle.
+    fn next_prerequisite(
+        &self,
+        lesson_id: &str,
+        visited: &mut HashSet<LessonId>,
+    ) -> Option<LessonState> {
+        if !visited.insert(lesson_id.to_string()) {
+            return None;
+        }
+        for prerequisite in self
+            .lesson_prerequisites
+            .get(lesson_id)
+            .into_iter()
+            .flatten()
+        {
+            if self.get_mastery_for_lesson(prerequisite) >= self.config.prerequisite_mastery {
+                continue;
+            }
+            if !self.is_lesson_unlocked(prerequisite) {
+                match self.next_prerequisite(prerequisite, visited) {
+                    Some(state) => return Some(state),
+                    None => continue,
+                }
+            }
+            if let Some(state) = self.q_table.keys().find(|(l, _)| l == prerequisite) {
+                return Some(state.clone());
+            }
         }
+        None
+    }
+
+    /// Set the version of the module's content that the q table's lessons are from.
+    pub fn with_content_version(mut self, content_version: u32) -> QTableAlgorithm {
+        self.content_version = content_version;
+        self
+    }
+
+    pub fn get_content_version(&self) -> u32 {
+        self.content_version
+    }
+
+    /// Carry the learner's progress over to a new version of the module's content,
+    /// given by the lessons of the new version. Each old lesson's q value, when it was
+    /// last practised and its recommended prompt level move to the new lesson it is
+    /// mapped to, and the lessons' prerequisites are taken from the new version. Where
+    /// several old lessons were merged into one, the new lesson gets the best of their q
+    /// values, the most recent practice and the most supportive prompt level. Old
+    /// lessons without a mapping are dropped.
+    ///
+    /// A lesson that is new in this version starts at the mean q value of the migrated
+    /// lessons at its difficulty level, so that what the learner has shown at that
+    /// level carries over to it, or 0 if there are none. The decay, non-attempt and
+    /// consecutive attempt counters are kept by difficulty level, so carry over as they
+    /// are.
+    pub fn migrate(
+        &mut self,
+        migration: &ContentMigration,
+        new_lessons: &LessonCatalogue,
+    ) -> Result<()> {
+        if self.content_version != migration.get_from_version() {
+            return Err(NeuroNudgeError::ContentVersionMismatch {
+                found: self.content_version,
+                expected: migration.get_from_version(),
+            });
+        }
+        // Check every mapping and the new prerequisites before changing anything, so
+        // that a failed migration leaves the q table as it was.
+        for new_lesson_id in migration.get_lesson_mappings().values() {
+            new_lessons.try_get(new_lesson_id)?;
+        }
+        let new_lesson_prerequisites = lesson_prerequisites(new_lessons)?;
+
+        let mut q_table = QTable::new();
+        for ((old_lesson_id, _), &value) in &self.q_table {
+            let Some(new_lesson_id) = migration.get_new_lesson_id(old_lesson_id) else {
+                continue;
+            };
+            let new_lesson = new_lessons.try_get(new_lesson_id)?;
+            let state = (
+                new_lesson_id.clone(),
+                new_lesson.get_difficulty_level().clone(),
+            );
+            let best_value = q_table.get(&state).map_or(value, |&v: &f32| v.max(value));
+            q_table.insert(state, best_value);
+        }
+
+        // The total and count of the migrated q values at each difficulty level.
+        let mut migrated_values: HashMap<DifficultyLevel, (f32, f32)> = HashMap::new();
+        for ((_, difficulty_level), &value) in &q_table {
+            let (total, count) = migrated_values
+                .entry(difficulty_level.clone())
+                .or_insert((0.0, 0.0));
+            *total += value;
+            *count += 1.0;
+        }
+        for new_lesson in new_lessons.get_lessons() {
+            let difficulty_level = new_lesson.get_difficulty_level();
+            let default_value = migrated_values
+                .get(difficulty_level)
+                .map_or(0.0, |(total, count)| total / count);
+            q_table
+                .entry((new_lesson.get_id().clone(), difficulty_level.clone()))
+                .or_insert(default_value);
+        }
+
+        let mut lesson_last_practised = HashMap::new();
+        for (old_lesson_id, &update) in &self.lesson_last_practised {
+            if let Some(new_lesson_id) = migration.get_new_lesson_id(old_lesson_id) {
+                let last_update = lesson_last_practised
+                    .entry(new_lesson_id.clone())
+                    .or_insert(update);
+                *last_update = (*last_update).max(update);
+            }
+        }
+
+        let mut recommended_prompt_levels = HashMap::new();
+        for (old_lesson_id, &prompt_level) in &self.recommended_prompt_levels {
+            if let Some(new_lesson_id) = migration.get_new_lesson_id(old_lesson_id) {
+                let level = recommended_prompt_levels
+                    .entry(new_lesson_id.clone())
+                    .or_insert(prompt_level);
+                *level = (*level).min(prompt_level);
+            }
+        }
+
+        self.q_table = q_table;
+        self.lesson_last_practised = lesson_last_practised;
+        self.recommended_prompt_levels = recommended_prompt_levels;
+        self.lesson_prerequisites = new_lesson_prerequisites;
+        self.content_version = migration.get_to_version();
+        Ok(())
+    }
+
+    pub fn get_lesson_selection(&self) -> &LessonSelection {
+        &self.lesson_selection
+    }
+
+    pub fn set_lesson_selection(&mut self, lesson_selection: LessonSelection) {
+        self.lesson_selection = lesson_selection;
     }
 
     pub fn get_strategy(&self) -> &Strategy {
         &self.strategy
     }
 
+    pub fn get_config(&self) -> &EngineConfig {
+        &self.config
+    }
+
     pub fn get_consecutive_attempts_for_difficulty(
         &self,
         difficulty_level: &DifficultyLevel,
-    ) -> &f32 {
-        self.consecutive_attempts.get(difficulty_level).unwrap()
+    ) -> Result<&f32> {
+        self.consecutive_attempts
+            .get(difficulty_level)
+            .ok_or_else(|| NeuroNudgeError::UntrackedDifficulty(difficulty_level.clone()))
     }
 
-    pub fn insert(&mut self, state: (Lesson, DifficultyLevel), value: f32) {
+    pub fn insert(&mut self, state: LessonState, value: f32) {
         self.q_table.insert(state, value);
     }
 
     /// Get the value of some state-action pair.
-    pub fn get(&self, state: &(Lesson, DifficultyLevel)) -> Option<&f32> {
+    pub fn get(&self, state: &LessonState) -> Option<&f32> {
         self.q_table.get(state)
     }
 
@@ -136,19 +377,158 @@ impl QTableAlgorithm {
         &self.id
     }
 
-    /// Determine if a particular difficulty level is weak in progress
-    fn is_weak_level(&self, difficulty_level: &DifficultyLevel) -> bool {
-        let current_value = self
-            .q_table
+    /// Check if any lesson at some difficulty level has been attempted.
+    pub fn has_attempted_difficulty(&self, difficulty_level: &DifficultyLevel) -> bool {
+        *self
+            .has_attempted_difficulty
+            .get(difficulty_level)
+            .unwrap_or(&false)
+    }
+
+    /// Get the prompt level recommended for the learner's next attempt at some lesson,
+    /// or `None` if no prompt levels have been recorded for it.
+    pub fn get_recommended_prompt_level(&self, lesson_id: &str) -> Option<PromptLevel> {
+        self.recommended_prompt_levels.get(lesson_id).copied()
+    }
+
+    /// Get the total number of lesson results that the q table has been updated with.
+    pub fn get_total_updates(&self) -> u64 {
+        self.total_updates
+    }
+
+    /// Get the mastery of some difficulty level, as per the mastery thresholds applied
+    /// to the best q value at that level.
+    pub fn get_mastery_for_difficulty(&self, difficulty_level: &DifficultyLevel) -> Mastery {
+        self.mastery_for_value(self.get_best_value_for_difficulty(difficulty_level))
+    }
+
+    /// Get the mastery that some value (a reward or q value) reaches, as per the
+    /// configured mastery thresholds.
+    fn mastery_for_value(&self, value: f32) -> Mastery {
+        let thresholds = &self.config.mastery_thresholds;
+        if value >= thresholds.full {
+            Mastery::Full
+        } else if value >= thresholds.competent {
+            Mastery::Competent
+        } else if value >= thresholds.basic {
+            Mastery::Basic
+        } else {
+            Mastery::None
+        }
+    }
+
+    /// Get the best q value of any lesson at some difficulty level, or 0 if there
+    /// are no lessons at that level.
+    pub fn get_best_value_for_difficulty(&self, difficulty_level: &DifficultyLevel) -> f32 {
+        self.q_table
             .iter()
             .filter(|((_, d), _)| d == difficulty_level)
             .map(|(_, &v)| v)
             .max_by(|x, y| x.partial_cmp(y).unwrap_or(std::cmp::Ordering::Equal))
-            .unwrap_or(0.0);
+            .unwrap_or(0.0)
+    }
+
+    /// Get all unlocked lessons at some difficulty level along with their q values,
+    /// ordered by lesson id so that choices between them don't depend on the q table's
+    /// hash order.
+    fn get_lessons_at_difficulty(
+        &self,
+        difficulty_level: &DifficultyLevel,
+    ) -> Vec<(&LessonId, f32)> {
+        let mut lessons: Vec<(&LessonId, f32)> = self
+            .q_table
+            .iter()
+            .filter(|((l, d), _)| d == difficulty_level && self.is_lesson_unlocked(l))
+            .map(|((l, _), &v)| (l, v))
+            .collect();
+        lessons.sort_by_key(|(a, _)| *a);
+        lessons
+    }
 
-        // Threshold for weakness, can be adjusted
-        let threshold = 0.5;
-        current_value <= threshold
+    /// The update count at which a lesson was last practised, offset by one so that
+    /// lessons never practised come before all others.
+    fn last_practised(&self, lesson_id: &LessonId) -> u64 {
+        self.lesson_last_practised
+            .get(lesson_id)
+            .map_or(0, |&update| update + 1)
+    }
+
+    /// Choose the lesson with the highest q value at some difficulty level.
+    /// Ties are broken by whichever lesson was practised least recently.
+    fn best_lesson_at_difficulty(&self, difficulty_level: &DifficultyLevel) -> Option<LessonState> {
+        self.get_lessons_at_difficulty(difficulty_level)
+            .iter()
+            .max_by(|(a, a_value), (b, b_value)| {
+                a_value
+                    .partial_cmp(b_value)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    // On equal values prefer the less recently practised lesson
+                    .then_with(|| self.last_practised(b).cmp(&self.last_practised(a)))
+            })
+            .map(|(lesson_id, _)| ((*lesson_id).clone(), difficulty_level.clone()))
+    }
+
+    /// Choose one of the lessons at some difficulty level using the given selection.
+    fn choose_lesson_at_difficulty<R: Rng + ?Sized>(
+        &self,
+        difficulty_level: &DifficultyLevel,
+        lesson_selection: &LessonSelection,
+        rng: &mut R,
+    ) -> Option<LessonState> {
+        let lessons = self.get_lessons_at_difficulty(difficulty_level);
+
+        let chosen = match lesson_selection {
+            LessonSelection::BestValue => return self.best_lesson_at_difficulty(difficulty_level),
+            LessonSelection::LeastRecentlyPractised => lessons
+                .iter()
+                .min_by_key(|(l, _)| self.last_practised(l))
+                .map(|(l, _)| *l),
+            LessonSelection::Exploration => {
+                if lessons.is_empty() {
+                    None
+                } else {
+                    let index = rng.gen_range(0..lessons.len());
+                    Some(lessons[index].0)
+                }
+            }
+        };
+
+        chosen.map(|lesson_id| (lesson_id.clone(), difficulty_level.clone()))
+    }
+
+    /// Choose one of the lessons at some difficulty level using the algorithm's lesson
+    /// selection. If every lesson at the level is still locked, choose a prerequisite
+    /// that unlocks one of them instead. Fails if the q table has no lessons at that
+    /// level.
+    fn choose_required_lesson_at_difficulty<R: Rng + ?Sized>(
+        &self,
+        difficulty_level: &DifficultyLevel,
+        rng: &mut R,
+    ) -> Result<LessonState> {
+        if let Some(state) =
+            self.choose_lesson_at_difficulty(difficulty_level, &self.lesson_selection, rng)
+        {
+            return Ok(state);
+        }
+
+        let mut locked_lessons: Vec<&LessonId> = self
+            .q_table
+            .keys()
+            .filter(|(_, d)| d == difficulty_level)
+            .map(|(l, _)| l)
+            .collect();
+        locked_lessons.sort();
+        locked_lessons
+            .into_iter()
+            .find_map(|lesson_id| self.next_prerequisite(lesson_id, &mut HashSet::new()))
+            .ok_or_else(|| NeuroNudgeError::NoLessonAtDifficulty(difficulty_level.clone()))
+    }
+
+    /// Determine if a particular difficulty level is weak in progress
+    fn is_weak_level(&self, difficulty_level: &DifficultyLevel) -> bool {
+        let current_value = self.get_best_value_for_difficulty(difficulty_level);
+
+        current_value <= self.config.weak_level_threshold
     }
 
     /// Find the weakest difficulty level by q value, but ensure that the
@@ -156,8 +536,10 @@ impl QTableAlgorithm {
     /// is the low q value, but if it's not true in the has_attempted_difficulty
     /// then it hasn't even been attempted.
     fn find_weaker_level(&self) -> Option<DifficultyLevel> {
-        self.decay_counters
-            .keys()
+        // Go through the levels from easiest to hardest, rather than in hash order, so
+        // that the same q table always gives the same level.
+        DIFFICULTY_LEVELS
+            .iter()
             // Find the weak levels
             .filter(|&level| self.is_weak_level(level))
             // Find the levels that have been attempted
@@ -177,13 +559,16 @@ impl QTableAlgorithm {
             })
     }
 
-    // Epsilon-greedy strategy to choose the next action
-    pub fn epsilon_greedy_action(
+    // Epsilon-greedy strategy to choose the next action.
+    // All randomness is drawn from the given rng, so a seeded rng gives reproducible
+    // recommendations.
+    pub fn epsilon_greedy_action<R: Rng + ?Sized>(
         &self,
-        state: &(Lesson, DifficultyLevel),
+        state: &LessonState,
         mastery_level: Option<Mastery>,
-    ) -> (Lesson, DifficultyLevel) {
-        let rand_value = rand::thread_rng().gen::<f32>();
+        rng: &mut R,
+    ) -> Result<LessonState> {
+        let rand_value = rng.gen::<f32>();
         if rand_value < self.epsilon {
             if self.strategy == Strategy::DecayingQValues
                 || self.strategy == Strategy::TraitSensitivity
@@ -192,43 +577,32 @@ impl QTableAlgorithm {
                 let weaker_level = self.find_weaker_level();
                 if let Some(level) = weaker_level {
                     // Return an action for the weaker level
-                    self.q_table
-                        .keys()
-                        .find(|(_, d)| d == &level)
-                        .unwrap()
-                        .clone()
+                    self.choose_required_lesson_at_difficulty(&level, rng)
                 } else {
                     // If no weaker level, choose the next difficulty level
-                    self.choose_next_difficulty(state, mastery_level)
+                    self.choose_next_difficulty(state, mastery_level, rng)
                 }
             } else {
                 // Exploration: choose the next difficulty level.
-                self.choose_next_difficulty(state, mastery_level)
+                self.choose_next_difficulty(state, mastery_level, rng)
             }
         } else {
             // Exploitation: choose the best-known action.
-            self.get_best_action(state)
-                .clone()
-                .unwrap_or_else(|| self.choose_next_difficulty(state, mastery_level))
+            match self.get_best_action(state) {
+                Some(best_action) => Ok(best_action),
+                None => self.choose_next_difficulty(state, mastery_level, rng),
+            }
         }
     }
 
     // Assuming we choose the next difficulty level.
-    fn choose_next_difficulty(
+    fn choose_next_difficulty<R: Rng + ?Sized>(
         &self,
-        state: &(Lesson, DifficultyLevel),
+        state: &LessonState,
         mastery_level: Option<Mastery>,
-    ) -> (Lesson, DifficultyLevel) {
-        let difficulties = [
-            DifficultyLevel::VeryEasy,
-            DifficultyLevel::Easy,
-            DifficultyLevel::Medium,
-            DifficultyLevel::Hard,
-            DifficultyLevel::VeryHard,
-            DifficultyLevel::Expert,
-            DifficultyLevel::Master,
-            DifficultyLevel::Grandmaster,
-        ];
+        rng: &mut R,
+    ) -> Result<LessonState> {
+        let difficulties = DIFFICULTY_LEVELS;
 
         let current_index = difficulties
             .iter()
@@ -244,34 +618,24 @@ impl QTableAlgorithm {
             && is_current_weak
         {
             // Balance between reinforcing a weak level and moving to a higher difficulty
-            return self
-                .q_table
-                .keys()
-                .find(|(_, d)| d == &current_difficulty)
-                .unwrap()
-                .clone();
+            return self.choose_required_lesson_at_difficulty(&current_difficulty, rng);
         }
 
         if self.strategy == Strategy::BaseQLearning {
             // No mastery level considered, simply choose next difficulty
             let next_index = current_index + 1;
             let next_index = next_index.min(difficulties.len() - 1); // Ensure index is within bounds
-            let next_index = next_index.max(0); // Ensure index is within bounds
 
             let next_difficulty = difficulties[next_index].clone();
 
-            let lesson_with_difficulty = self.q_table.keys().find(|(_, d)| d == &next_difficulty);
-
-            (
-                lesson_with_difficulty.unwrap().0.clone(),
-                difficulties[next_index].clone(),
-            )
+            self.choose_required_lesson_at_difficulty(&next_difficulty, rng)
         } else {
-            let mut next_index = match mastery_level.unwrap() {
+            let mastery_level = mastery_level.ok_or(NeuroNudgeError::MissingMasteryLevel)?;
+            let mut next_index = match mastery_level {
                 Mastery::Full => current_index + 1, // Move up one level for full mastery
-                // With a random probability of 0.6, move up one level for competent mastery
+                // With some probability (0.6 by default), move up one level for competent mastery
                 Mastery::Competent => {
-                    if rand::thread_rng().gen::<f32>() < 0.6 {
+                    if rng.gen::<f32>() < self.config.competent_advance_probability {
                         current_index + 1
                     } else {
                         current_index
@@ -289,59 +653,43 @@ impl QTableAlgorithm {
             };
 
             next_index = next_index.min(difficulties.len() - 1); // Ensure index is within bounds
-            next_index = next_index.max(0); // Ensure index is within bounds
             let next_difficulty = difficulties[next_index].clone();
 
-            let lesson_with_difficulty = self.q_table.keys().find(|(_, d)| d == &next_difficulty);
-
-            (
-                lesson_with_difficulty.unwrap().0.clone(),
-                difficulties[next_index].clone(),
-            )
+            self.choose_required_lesson_at_difficulty(&next_difficulty, rng)
         }
     }
 
     /// Update the value of some state-action pair, based on a lesson result
-    /// from a learner.
-    pub fn update(
+    /// from a learner. The rng is used to estimate which lesson would come next.
+    pub fn update<R: Rng + ?Sized>(
         &mut self,
-        state: (Lesson, DifficultyLevel),
+        state: LessonState,
         lesson_result: &LessonResult,
-    ) -> Option<Mastery> {
+        rng: &mut R,
+    ) -> Result<Option<Mastery>> {
         let old_value = self.q_table.get(&state).unwrap_or(&0.0);
 
         self.has_attempted_difficulty.insert(state.1.clone(), true);
 
+        self.total_updates += 1;
+        self.lesson_last_practised
+            .insert(state.0.clone(), self.total_updates);
+
         // Update the consecutive attempts counter
-        self.consecutive_attempts = self
-            .consecutive_attempts
-            .iter()
-            .map(|(d, &v)| {
-                if d != &state.1 {
-                    (d.clone(), 0.0)
-                } else {
-                    (d.clone(), v + 1.0)
-                }
-            })
-            .collect();
+        for (d, v) in self.consecutive_attempts.iter_mut() {
+            if d != &state.1 {
+                *v = 0.0;
+            } else {
+                *v += 1.0;
+            }
+        }
 
         let lesson_difficulty = lesson_result.get_difficulty_level();
 
-        let difficulty_weight = match lesson_difficulty {
-            DifficultyLevel::VeryEasy => 0.2,
-            DifficultyLevel::Easy => 0.3,
-            DifficultyLevel::Medium => 0.4,
-            DifficultyLevel::Hard => 0.6,
-            DifficultyLevel::VeryHard => 0.7,
-            DifficultyLevel::Expert => 0.75,
-            DifficultyLevel::Master => 0.775,
-            DifficultyLevel::Grandmaster => 0.8,
-        };
+        let difficulty_weight = *self.config.difficulty_reward_weights.get(lesson_difficulty);
 
         let total_time_taken = lesson_result.get_time_taken() as f32;
-        let total_attempts = lesson_result.get_attempted_questions().len() as f32;
-        let total_incorrect_attempts = lesson_result.get_total_incorrect_attempts();
-        let total_hints_requested = lesson_result.get_total_hints_requested();
+        let question_attempts = lesson_result.get_attempted_questions();
 
         // Weights for each factor (I might adjust these further based on importance)
         let time_taken_weight = 0.3 * difficulty_weight;
@@ -351,7 +699,8 @@ impl QTableAlgorithm {
         // Overall reward calculation - the less time someone takes, the less incorrect they are, and less hints they request,
         // the higher the reward should be overall
 
-        // The time taken is currently simulated as follows:
+        // The expected time ranges are configurable, and by default match how the time
+        // taken is currently simulated:
         // DifficultyLevel::VeryEasy => // Random time between 5 to 10 seconds.
         // DifficultyLevel::Easy => // Random time between 10 to 15 seconds.
         // DifficultyLevel::Medium => // Random time between 20 to 30 seconds.
@@ -367,16 +716,7 @@ impl QTableAlgorithm {
         // a high reward and positive outcome.
 
         // Hence, calculate the time taken reward as follows:
-        let time_taken_range_for_difficulty = match state.1 {
-            DifficultyLevel::VeryEasy => (5.0, 10.0),
-            DifficultyLevel::Easy => (10.0, 15.0),
-            DifficultyLevel::Medium => (20.0, 30.0),
-            DifficultyLevel::Hard => (30.0, 40.0),
-            DifficultyLevel::VeryHard => (40.0, 50.0),
-            DifficultyLevel::Expert => (50.0, 60.0),
-            DifficultyLevel::Master => (60.0, 70.0),
-            DifficultyLevel::Grandmaster => (70.0, 80.0),
-        };
+        let time_taken_range_for_difficulty = *self.config.expected_time_ranges.get(&state.1);
 
         let time_taken_reward = if total_time_taken <= time_taken_range_for_difficulty.0 {
             1.0
@@ -403,43 +743,86 @@ impl QTableAlgorithm {
             1.0 - (penalty / time_taken_range)
         };
 
-        // Calculate the incorrect attempts and hints requested rewards as follows:
-        let incorrect_attempts_reward = if total_incorrect_attempts == 0 {
-            1.0
-        } else {
-            // Here, suppose someone had 3 incorrect attempts out of 10 total attempts. So, the reward should be 1.0 - 0.3 = 0.7.
-            let incorrect_attempts_percentage = total_incorrect_attempts as f32 / total_attempts;
-            1.0 - incorrect_attempts_percentage
+        // Calculate the incorrect attempts and hints requested rewards per question, and
+        // average them over the questions, so that a question that took many attempts
+        // only counts against its own share of the lesson. Questions scored by an
+        // instructor can be weighted differently to those scored automatically.
+        let question_weight = |question_attempt: &QuestionAttempt| {
+            if question_attempt.is_instructor_scored() {
+                self.config.instructor_scored_weight
+            } else {
+                self.config.self_scored_weight
+            }
         };
-
-        let hints_requested_reward = if total_hints_requested == 0 {
-            1.0
-        } else {
-            // Here, suppose someone requested 2 hints out of 10 total attempts. So, the reward should be 1.0 - 0.2 = 0.8.
-            let hints_requested_percentage = total_hints_requested as f32 / total_attempts;
-            1.0 - hints_requested_percentage
+        let total_question_weight: f32 = question_attempts.iter().map(question_weight).sum();
+        let mean_over_questions = |question_reward: &dyn Fn(&QuestionAttempt) -> f32| {
+            if total_question_weight <= 0.0 {
+                1.0
+            } else {
+                question_attempts
+                    .iter()
+                    .map(|question_attempt| {
+                        question_weight(question_attempt) * question_reward(question_attempt)
+                    })
+                    .sum::<f32>()
+                    / total_question_weight
+            }
         };
 
+        // A correct answer also only gets the credit of the prompt level it was given at,
+        // of the instructor's verdict if they scored it, and of any partial credit its
+        // response earned, so that independent and fully correct answers get the most
+        // credit.
+        let incorrect_attempts_reward = mean_over_questions(&|question_attempt| {
+            // Here, suppose someone had 3 incorrect attempts out of 4 total attempts at a question. So, the
+            // reward for that question should be 1.0 - 0.75 = 0.25.
+            let total_attempts = (*question_attempt.get_total_attempts()).max(1) as f32;
+            let prompt_level_credit = question_attempt
+                .get_prompt_level()
+                .map_or(1.0, |prompt_level| {
+                    self.config.prompt_level_credits.get(&prompt_level)
+                });
+            let verdict_credit = question_attempt
+                .get_instructor_evaluation()
+                .as_ref()
+                .map_or(1.0, |evaluation| {
+                    self.config.verdict_credit(evaluation.get_verdict())
+                });
+            (1.0 - *question_attempt.get_incorrect_attempts() as f32 / total_attempts)
+                * prompt_level_credit
+                * verdict_credit
+                * question_attempt.get_credit().unwrap_or(1.0)
+        });
+
+        let hints_requested_reward = mean_over_questions(&|question_attempt| {
+            // Here, suppose someone requested 1 hint over 4 total attempts at a question. So, the reward for
+            // that question should be 1.0 - 0.25 = 0.75.
+            let total_attempts = (*question_attempt.get_total_attempts()).max(1) as f32;
+            let hints_requested = question_attempt.get_hints_requested().unwrap_or(0) as f32;
+            (1.0 - hints_requested / total_attempts).max(0.0)
+        });
+
         // Calculate the overall reward as follows:
         let mut reward = (time_taken_weight * time_taken_reward
             + incorrect_attempts_weight * incorrect_attempts_reward
             + hints_requested_weight * hints_requested_reward)
             / difficulty_weight;
 
-        reward = reward.max(-1.0).min(1.0);
+        reward = reward.clamp(-1.0, 1.0);
 
         // Adjust the reward based on mastery thresholds, if strategy isn't basic q learning
         let mut mastery_level: Option<Mastery> = None;
         if self.strategy != Strategy::BaseQLearning {
-            mastery_level = if reward >= FULL_MASTERY_THRESHOLD {
-                Some(Mastery::Full)
-            } else if reward >= COMPETENT_MASTERY_THRESHOLD {
-                Some(Mastery::Competent)
-            } else if reward >= BASIC_MASTERY_THRESHOLD {
-                Some(Mastery::Basic)
-            } else {
-                Some(Mastery::None)
-            };
+            mastery_level = Some(self.mastery_for_value(reward));
+
+            // Answering correctly after a hint shows competence, but Full mastery means the
+            // learner can answer independently.
+            if mastery_level == Some(Mastery::Full)
+                && lesson_result.get_independent_answer_share()
+                    < self.config.full_mastery_independence
+            {
+                mastery_level = Some(Mastery::Competent);
+            }
 
             reward = match mastery_level {
                 Some(Mastery::Full) => 1.0, // Give full reward for the complete mastery
@@ -449,22 +832,28 @@ impl QTableAlgorithm {
             };
         }
 
-        let (next_state, _) = self.choose_next_difficulty(&state, mastery_level.clone());
-
-        let next_max = self
-            .q_table
-            .iter()
-            .filter(|((s, _), _)| s == &next_state)
-            .map(|(_, &v)| v)
-            .max_by(|x, y| x.partial_cmp(y).unwrap_or(std::cmp::Ordering::Equal))
-            .unwrap_or(0.0);
+        // If the module has no lesson at the next difficulty level, there is no future
+        // value to account for.
+        let next_max = match self.choose_next_difficulty(&state, mastery_level.clone(), rng) {
+            Ok((next_state, _)) => self
+                .q_table
+                .iter()
+                .filter(|((s, _), _)| s == &next_state)
+                .map(|(_, &v)| v)
+                .max_by(|x, y| x.partial_cmp(y).unwrap_or(std::cmp::Ordering::Equal))
+                .unwrap_or(0.0),
+            Err(NeuroNudgeError::NoLessonAtDifficulty(_)) => 0.0,
+            Err(error) => return Err(error),
+        };
 
-        let new_value =
-            old_value + self.learning_rate * (reward + self.discount_factor * next_max - old_value);
+        let new_value = old_value
+            + self.config.learning_rate
+                * (reward + self.config.discount_factor * next_max - old_value);
 
         self.q_table.insert(state.clone(), new_value.min(1.0)); // Ensure that the value is between 0 and 1
 
         self.update_difficulty_non_attempts(lesson_difficulty.clone());
+        self.update_recommended_prompt_level(&state.0, lesson_result);
 
         // If we're in strategy 3 (decaying q values) or 4 (trait sensitivty) then apply decay
         if self.strategy == Strategy::DecayingQValues || self.strategy == Strategy::TraitSensitivity
@@ -472,89 +861,358 @@ impl QTableAlgorithm {
             self.apply_decay();
         }
 
-        mastery_level
+        Ok(mastery_level)
     }
 
     /// Get the best action for some state.
-    pub fn get_best_action(
-        &self,
-        state: &(Lesson, DifficultyLevel),
-    ) -> Option<(Lesson, DifficultyLevel)> {
-        let mut best_action = None;
-        let mut best_value = f32::MIN;
-        for ((l, difficulty_level), &value) in &self.q_table {
-            if state.1 == *difficulty_level && value > best_value {
-                best_action = Some((l.clone(), difficulty_level.clone()));
-                best_value = value;
-            }
-        }
-        best_action
+    /// This is the lesson with the highest q value at the state's difficulty level.
+    pub fn get_best_action(&self, state: &LessonState) -> Option<LessonState> {
+        self.best_lesson_at_difficulty(&state.1)
     }
 
     /// Get all state-action pairs in the Q-Table.
-    pub fn get_lesson_difficulty_pairs(&self) -> Vec<(&(Lesson, DifficultyLevel), &f32)> {
+    pub fn get_lesson_difficulty_pairs(&self) -> Vec<(&LessonState, &f32)> {
         self.q_table.iter().collect()
     }
 
     /// Check if the Q-Table contains a specific state-action pair.
-    pub fn has_lesson_difficulty_pair(
-        &self,
-        state_action_pair: &(Lesson, DifficultyLevel),
-    ) -> bool {
+    pub fn has_lesson_difficulty_pair(&self, state_action_pair: &LessonState) -> bool {
         self.q_table.contains_key(state_action_pair)
     }
 
-    fn update_difficulty_non_attempts(&mut self, attempted_difficulty_level: DifficultyLevel) {
-        self.total_difficulty_non_attempts = self
-            .total_difficulty_non_attempts
+    /// Recommend the prompt level for the next attempt at a lesson, fading prompts as the
+    /// learner succeeds. The lesson's prompt level is the most support given for any of
+    /// its questions. If enough questions were answered correctly on the first try, the
+    /// next attempt gets less support, and if too few were, it gets more.
+    fn update_recommended_prompt_level(
+        &mut self,
+        lesson_id: &LessonId,
+        lesson_result: &LessonResult,
+    ) {
+        let prompted_attempts: Vec<&QuestionAttempt> = lesson_result
+            .get_attempted_questions()
             .iter()
-            .map(|(d, &v)| {
-                let has_attempted = self.has_attempted_difficulty.get(d).unwrap_or(&false);
-                if !has_attempted {
-                    (d.clone(), v)
-                } else if d == &attempted_difficulty_level {
-                    (d.clone(), 0.0) // Reset the counter for the difficulty level that was attempted
-                } else {
-                    (d.clone(), v + 1.0)
-                }
-            })
+            .filter(|question_attempt| question_attempt.get_prompt_level().is_some())
             .collect();
+        let Some(prompt_level) = prompted_attempts
+            .iter()
+            .filter_map(|question_attempt| *question_attempt.get_prompt_level())
+            .min()
+        else {
+            return;
+        };
+
+        let first_try_accuracy = prompted_attempts
+            .iter()
+            .filter(|question_attempt| *question_attempt.get_incorrect_attempts() == 0)
+            .count() as f32
+            / prompted_attempts.len() as f32;
+
+        let next_prompt_level = if first_try_accuracy >= self.config.prompt_fade_accuracy {
+            prompt_level.fade()
+        } else if first_try_accuracy < self.config.prompt_support_accuracy {
+            prompt_level.support()
+        } else {
+            prompt_level
+        };
+        self.recommended_prompt_levels
+            .insert(lesson_id.clone(), next_prompt_level);
+    }
+
+    fn update_difficulty_non_attempts(&mut self, attempted_difficulty_level: DifficultyLevel) {
+        for (d, v) in self.total_difficulty_non_attempts.iter_mut() {
+            let has_attempted = self.has_attempted_difficulty.get(d).unwrap_or(&false);
+            if !has_attempted {
+                continue;
+            } else if d == &attempted_difficulty_level {
+                *v = 0.0; // Reset the counter for the difficulty level that was attempted
+            } else {
+                *v += 1.0;
+            }
+        }
     }
 
     pub fn apply_decay(&mut self) {
         // Apply decay to Q-values for difficulty levels based on decay_counters
         // Adjust the rate of decay or interval for decay events using an exponential backoff strategy
-        self.q_table = self
-            .q_table
-            .iter()
-            .map(|((l, d), &v)| {
-                let non_attempts_counter =
-                    self.total_difficulty_non_attempts.get(d).unwrap_or(&0.0);
-
-                let required_non_attempts_to_apply_decay = match d {
-                    DifficultyLevel::VeryEasy => 2000.0,
-                    DifficultyLevel::Easy => 1750.0,
-                    DifficultyLevel::Medium => 1600.0,
-                    DifficultyLevel::Hard => 1400.0,
-                    DifficultyLevel::VeryHard => 1200.0,
-                    DifficultyLevel::Expert => 1050.0,
-                    DifficultyLevel::Master => 900.0,
-                    DifficultyLevel::Grandmaster => 750.0,
-                };
-
-                let decay_counter = self.decay_counters.get(d).unwrap_or(&0.0);
-                let do_decay = non_attempts_counter >= &required_non_attempts_to_apply_decay
-                    && decay_counter > &0.0;
-
-                if do_decay {
-                    let decay_rate = 1.0 / decay_counter;
-                    self.decay_counters.insert(d.clone(), decay_counter - 1.0);
-                    self.total_difficulty_non_attempts.insert(d.clone(), 0.0);
-                    ((l.clone(), d.clone()), v * decay_rate)
-                } else {
-                    ((l.clone(), d.clone()), v)
+        for d in &DIFFICULTY_LEVELS {
+            let non_attempts_counter = *self.total_difficulty_non_attempts.get(d).unwrap_or(&0.0);
+
+            let required_non_attempts_to_apply_decay = *self.config.non_attempt_thresholds.get(d);
+
+            let decay_counter = *self.decay_counters.get(d).unwrap_or(&0.0);
+            let do_decay =
+                non_attempts_counter >= required_non_attempts_to_apply_decay && decay_counter > 0.0;
+
+            if do_decay {
+                // Decay every lesson at the level together, so that which lessons decay
+                // doesn't depend on the order of the q table.
+                let decay_rate = 1.0 / decay_counter;
+                self.decay_counters.insert(d.clone(), decay_counter - 1.0);
+                self.total_difficulty_non_attempts.insert(d.clone(), 0.0);
+                for ((_, level), v) in self.q_table.iter_mut() {
+                    if level == d {
+                        *v *= decay_rate;
+                    }
                 }
-            })
-            .collect();
+            }
+        }
+    }
+}
+
+/// Get the prerequisites of each lesson that has any, failing if a prerequisite isn't
+/// one of the lessons or if the prerequisites form a cycle.
+fn lesson_prerequisites(lessons: &LessonCatalogue) -> Result<HashMap<LessonId, Vec<LessonId>>> {
+    let graph = PrerequisiteGraph::for_lessons(lessons.get_lessons());
+    if let Some((_, prerequisite)) = graph.missing_prerequisites().first() {
+        return Err(NeuroNudgeError::LessonNotFound((*prerequisite).clone()));
+    }
+    graph.check_acyclic()?;
+
+    Ok(lessons
+        .get_lessons()
+        .iter()
+        .filter(|lesson| !lesson.get_prerequisites().is_empty())
+        .map(|lesson| (lesson.get_id().clone(), lesson.get_prerequisites().clone()))
+        .collect())
+}
+
+/// Serde encoding for the QTable.
+/// The QTable is keyed by a (LessonId, DifficultyLevel) tuple, which can't be used as
+/// a key in formats like JSON where keys must be strings. So the table is encoded
+/// as a list of entries instead, each carrying its key alongside its value.
+#[cfg(feature = "serde")]
+mod q_table_serde {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::QTable;
+    use crate::content::{DifficultyLevel, LessonId};
+
+    #[derive(Serialize)]
+    struct QTableEntryRef<'a> {
+        lesson_id: &'a LessonId,
+        difficulty_level: &'a DifficultyLevel,
+        value: f32,
+    }
+
+    #[derive(Deserialize)]
+    struct QTableEntry {
+        lesson_id: LessonId,
+        difficulty_level: DifficultyLevel,
+        value: f32,
+    }
+
+    pub fn serialize<S: Serializer>(q_table: &QTable, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            q_table
+                .iter()
+                .map(|((lesson_id, difficulty_level), &value)| QTableEntryRef {
+                    lesson_id,
+                    difficulty_level,
+                    value,
+                }),
+        )
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<QTable, D::Error> {
+        let entries = Vec::<QTableEntry>::deserialize(deserializer)?;
+        Ok(entries
+            .into_iter()
+            .map(|entry| ((entry.lesson_id, entry.difficulty_level), entry.value))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::content::Lesson;
+
+    fn q_table() -> QTableAlgorithm {
+        QTableAlgorithm::new(None, 0.1, Strategy::BaseQLearning, EngineConfig::default()).unwrap()
+    }
+
+    /// A q table with three lessons at Easy, and one at Medium.
+    fn q_table_with_lessons(values: [f32; 3]) -> QTableAlgorithm {
+        let mut algorithm = q_table();
+        for (lesson_id, value) in ["a", "b", "c"].into_iter().zip(values) {
+            algorithm.insert((lesson_id.to_string(), DifficultyLevel::Easy), value);
+        }
+        algorithm.insert(("d".to_string(), DifficultyLevel::Medium), 1.0);
+        algorithm
+    }
+
+    fn choose(algorithm: &QTableAlgorithm, lesson_selection: LessonSelection) -> LessonId {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (lesson_id, difficulty_level) = algorithm
+            .choose_lesson_at_difficulty(&DifficultyLevel::Easy, &lesson_selection, &mut rng)
+            .unwrap();
+        assert_eq!(difficulty_level, DifficultyLevel::Easy);
+        lesson_id
+    }
+
+    #[test]
+    fn best_value_chooses_the_highest_q_value_at_the_level() {
+        let algorithm = q_table_with_lessons([0.2, 0.6, 0.4]);
+        assert_eq!(choose(&algorithm, LessonSelection::BestValue), "b");
+    }
+
+    #[test]
+    fn best_value_breaks_ties_by_least_recently_practised() {
+        let mut algorithm = q_table_with_lessons([0.5, 0.5, 0.1]);
+        algorithm.lesson_last_practised.insert("a".to_string(), 3);
+        algorithm.lesson_last_practised.insert("b".to_string(), 1);
+        assert_eq!(choose(&algorithm, LessonSelection::BestValue), "b");
+    }
+
+    #[test]
+    fn least_recently_practised_chooses_lessons_never_practised_first() {
+        let mut algorithm = q_table_with_lessons([0.9, 0.1, 0.5]);
+        algorithm.lesson_last_practised.insert("a".to_string(), 1);
+        algorithm.lesson_last_practised.insert("c".to_string(), 2);
+        assert_eq!(
+            choose(&algorithm, LessonSelection::LeastRecentlyPractised),
+            "b"
+        );
+
+        algorithm.lesson_last_practised.insert("b".to_string(), 3);
+        assert_eq!(
+            choose(&algorithm, LessonSelection::LeastRecentlyPractised),
+            "a"
+        );
+    }
+
+    #[test]
+    fn exploration_chooses_every_lesson_at_the_level() {
+        let algorithm = q_table_with_lessons([0.9, 0.1, 0.5]);
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut chosen = HashSet::new();
+        for _ in 0..100 {
+            let (lesson_id, difficulty_level) = algorithm
+                .choose_lesson_at_difficulty(
+                    &DifficultyLevel::Easy,
+                    &LessonSelection::Exploration,
+                    &mut rng,
+                )
+                .unwrap();
+            assert_eq!(difficulty_level, DifficultyLevel::Easy);
+            chosen.insert(lesson_id);
+        }
+        let mut chosen: Vec<_> = chosen.into_iter().collect();
+        chosen.sort();
+        assert_eq!(chosen, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn no_lesson_is_chosen_at_a_level_without_lessons() {
+        let algorithm = q_table_with_lessons([0.1, 0.2, 0.3]);
+        let mut rng = StdRng::seed_from_u64(0);
+        for lesson_selection in [
+            LessonSelection::BestValue,
+            LessonSelection::LeastRecentlyPractised,
+            LessonSelection::Exploration,
+        ] {
+            assert_eq!(
+                algorithm.choose_lesson_at_difficulty(
+                    &DifficultyLevel::Hard,
+                    &lesson_selection,
+                    &mut rng
+                ),
+                None
+            );
+        }
+        assert!(matches!(
+            algorithm.choose_required_lesson_at_difficulty(&DifficultyLevel::Hard, &mut rng),
+            Err(NeuroNudgeError::NoLessonAtDifficulty(DifficultyLevel::Hard))
+        ));
+    }
+
+    #[test]
+    fn cyclic_lesson_prerequisites_are_rejected() {
+        let lesson = |id: &str, prerequisite: &str| {
+            Lesson::new_with_id(
+                id.to_string(),
+                id.to_string(),
+                vec![],
+                DifficultyLevel::Easy,
+                "module".to_string(),
+            )
+            .with_prerequisites(vec![prerequisite.to_string()])
+        };
+        let lessons = LessonCatalogue::new(vec![lesson("a", "b"), lesson("b", "a")]);
+
+        let mut algorithm = q_table_with_lessons([0.1, 0.2, 0.3]);
+        assert!(matches!(
+            algorithm.set_lesson_prerequisites(&lessons),
+            Err(NeuroNudgeError::PrerequisiteCycle(_))
+        ));
+
+        // As they would be if restored from a snapshot.
+        algorithm.lesson_prerequisites = HashMap::from([
+            ("a".to_string(), vec!["b".to_string()]),
+            ("b".to_string(), vec!["a".to_string()]),
+        ]);
+        assert!(matches!(
+            algorithm.check_lesson_prerequisites(),
+            Err(NeuroNudgeError::PrerequisiteCycle(_))
+        ));
+    }
+
+    #[test]
+    fn cyclic_lesson_prerequisites_lock_their_lessons_without_looping() {
+        let mut algorithm = q_table_with_lessons([0.1, 0.2, 0.3]);
+        algorithm.lesson_prerequisites = HashMap::from([
+            ("a".to_string(), vec!["b".to_string()]),
+            ("b".to_string(), vec!["c".to_string()]),
+            ("c".to_string(), vec!["a".to_string()]),
+        ]);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        assert!(matches!(
+            algorithm.choose_required_lesson_at_difficulty(&DifficultyLevel::Easy, &mut rng),
+            Err(NeuroNudgeError::NoLessonAtDifficulty(DifficultyLevel::Easy))
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn q_table_is_encoded_as_a_list_of_entries() {
+        let mut algorithm = q_table();
+        algorithm.insert(("circles".to_string(), DifficultyLevel::VeryEasy), 0.25);
+        algorithm.insert(("squares".to_string(), DifficultyLevel::Hard), -0.5);
+
+        let json = serde_json::to_value(&algorithm).unwrap();
+        let mut entries = json["q_table"].as_array().unwrap().clone();
+        entries.sort_by_key(|entry| entry["lesson_id"].as_str().unwrap().to_string());
+        assert_eq!(
+            entries,
+            vec![
+                serde_json::json!({
+                    "lesson_id": "circles",
+                    "difficulty_level": "VeryEasy",
+                    "value": 0.25
+                }),
+                serde_json::json!({
+                    "lesson_id": "squares",
+                    "difficulty_level": "Hard",
+                    "value": -0.5
+                }),
+            ]
+        );
+
+        let restored: QTableAlgorithm = serde_json::from_value(json).unwrap();
+        assert_eq!(restored, algorithm);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn empty_q_table_round_trips() {
+        let algorithm = q_table();
+        let json = serde_json::to_string(&algorithm).unwrap();
+        let restored: QTableAlgorithm = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, algorithm);
     }
 }
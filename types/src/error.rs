@@ -0,0 +1,204 @@
+//! This defines the errors that can occur across NeuroNudge.
+//!
+//! Anything that can fail on user provided data - parsing, malformed content, a q table
+//! without a lesson for some difficulty level and so on - returns a `NeuroNudgeError`
+//! rather than panicking, so that a service calling into the engine can handle it.
+//!
+
+use std::fmt;
+use std::io;
+
+use crate::content::{DifficultyLevel, LessonId};
+
+#[derive(Debug)]
+pub enum NeuroNudgeError {
+    /// A string that isn't the name of any difficulty level.
+    InvalidDifficultyLevel(String),
+    /// A lesson that has no questions in it.
+    EmptyLesson {
+        lesson_id: LessonId,
+    },
+    /// A question that doesn't define the ASD traits parameters it requires.
+    MissingAsdTraits {
+        question_id: String,
+    },
+    /// A learner that hasn't been given any lesson plan yet.
+    NoLessonPlan {
+        learner_id: String,
+    },
+    /// A lesson plan that has no lessons in it.
+    EmptyLessonPlan {
+        lesson_plan_id: String,
+    },
+    /// A difficulty level that the q table holds no lessons for.
+    NoLessonAtDifficulty(DifficultyLevel),
+    /// A difficulty level that the q table isn't tracking attempts for.
+    UntrackedDifficulty(DifficultyLevel),
+    /// A mastery level was needed to choose the next difficulty, but none was given.
+    MissingMasteryLevel,
+    /// A lesson that couldn't be found by its id.
+    LessonNotFound(LessonId),
+    /// A question whose answer doesn't make sense with its options, e.g. an answer
+    /// index that is out of range.
+    InvalidQuestion {
+        question_id: String,
+        reason: String,
+    },
+    /// A response that can't answer its question, e.g. an option index that is out of
+    /// range.
+    InvalidResponse {
+        question_id: String,
+        reason: String,
+    },
+    /// A content file that couldn't be loaded, with the field that the problem was
+    /// found at, e.g. `modules[0].lessons[1].name`, and its line if known.
+    InvalidContent {
+        field: String,
+        line: Option<usize>,
+        reason: String,
+    },
+    /// A q table that doesn't hold the version of a module's content that was expected,
+    /// e.g. when migrating it from some other version.
+    ContentVersionMismatch {
+        found: u32,
+        expected: u32,
+    },
+    /// Prerequisites that form a cycle, given by the ids along it.
+    PrerequisiteCycle(Vec<String>),
+    /// A q table id that can't be used as the name of a snapshot file, e.g. one with a
+    /// path separator in it.
+    InvalidSnapshotId(String),
+    /// A snapshot written with a format version that isn't supported.
+    UnsupportedSnapshotVersion {
+        found: u32,
+        expected: u32,
+    },
+    /// An engine config with values the engine can't use.
+    InvalidConfig(String),
+    /// Data that couldn't be serialized or deserialized.
+    Serialization(String),
+    Io(io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, NeuroNudgeError>;
+
+impl fmt::Display for NeuroNudgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NeuroNudgeError::InvalidDifficultyLevel(difficulty_level) => {
+                write!(f, "Invalid difficulty level: {:?}", difficulty_level)
+            }
+            NeuroNudgeError::EmptyLesson { lesson_id } => {
+                write!(f, "Lesson {} has no questions", lesson_id)
+            }
+            NeuroNudgeError::MissingAsdTraits { question_id } => {
+                write!(f, "Question {} has no ASD traits parameters", question_id)
+            }
+            NeuroNudgeError::NoLessonPlan { learner_id } => {
+                write!(f, "Learner {} has no lesson plan", learner_id)
+            }
+            NeuroNudgeError::EmptyLessonPlan { lesson_plan_id } => {
+                write!(f, "Lesson plan {} has no lessons", lesson_plan_id)
+            }
+            NeuroNudgeError::NoLessonAtDifficulty(difficulty_level) => {
+                write!(f, "No lesson in the q table at {:?}", difficulty_level)
+            }
+            NeuroNudgeError::UntrackedDifficulty(difficulty_level) => {
+                write!(
+                    f,
+                    "The q table isn't tracking attempts at {:?}",
+                    difficulty_level
+                )
+            }
+            NeuroNudgeError::MissingMasteryLevel => {
+                write!(f, "A mastery level is required by this strategy")
+            }
+            NeuroNudgeError::LessonNotFound(lesson_id) => {
+                write!(f, "Lesson {} could not be found", lesson_id)
+            }
+            NeuroNudgeError::InvalidQuestion {
+                question_id,
+                reason,
+            } => {
+                write!(f, "Question {} is invalid: {}", question_id, reason)
+            }
+            NeuroNudgeError::InvalidResponse {
+                question_id,
+                reason,
+            } => {
+                write!(
+                    f,
+                    "Invalid response to question {}: {}",
+                    question_id, reason
+                )
+            }
+            NeuroNudgeError::InvalidContent {
+                field,
+                line: Some(line),
+                reason,
+            } => {
+                write!(
+                    f,
+                    "Invalid content at {} (line {}): {}",
+                    field, line, reason
+                )
+            }
+            NeuroNudgeError::InvalidContent {
+                field,
+                line: None,
+                reason,
+            } => {
+                write!(f, "Invalid content at {}: {}", field, reason)
+            }
+            NeuroNudgeError::ContentVersionMismatch { found, expected } => {
+                write!(
+                    f,
+                    "The q table is for content version {} (expected {})",
+                    found, expected
+                )
+            }
+            NeuroNudgeError::PrerequisiteCycle(cycle) => {
+                write!(f, "Prerequisites form a cycle: {}", cycle.join(" -> "))
+            }
+            NeuroNudgeError::InvalidSnapshotId(q_table_id) => {
+                write!(f, "{:?} can't be used as a snapshot file name", q_table_id)
+            }
+            NeuroNudgeError::UnsupportedSnapshotVersion { found, expected } => {
+                write!(
+                    f,
+                    "Unsupported q table snapshot version {} (expected {})",
+                    found, expected
+                )
+            }
+            NeuroNudgeError::InvalidConfig(message) => {
+                write!(f, "Invalid engine config: {}", message)
+            }
+            NeuroNudgeError::Serialization(message) => {
+                write!(f, "Serialization failed: {}", message)
+            }
+            NeuroNudgeError::Io(error) => write!(f, "IO error: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for NeuroNudgeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NeuroNudgeError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for NeuroNudgeError {
+    fn from(error: io::Error) -> Self {
+        NeuroNudgeError::Io(error)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for NeuroNudgeError {
+    fn from(error: serde_json::Error) -> Self {
+        NeuroNudgeError::Serialization(error.to_string())
+    }
+}
@@ -1,16 +1,19 @@
 use crate::content::{Lesson, LessonPlan};
+use crate::error::{NeuroNudgeError, Result};
 use uuid::Uuid;
 
 // ASD Traits
 // The ASD traits are a set of measurements that are used to determine
 // the similarity between learners.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Communicability {
     Verbal,
     NonVerbal,
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CommunicationLevel {
     High,
     Medium,
@@ -18,6 +21,7 @@ pub enum CommunicationLevel {
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MotorSkills {
     VeryHigh,
     High,
@@ -26,6 +30,7 @@ pub enum MotorSkills {
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ASDTraits {
     learner_id: String,
     attention_span: i32,
@@ -42,8 +47,13 @@ pub trait ASDTraitComparison {
 
 impl ASDTraitComparison for ASDTraits {
     fn calculate_alignment(&self, other: &ASDTraits) -> f32 {
-        let attention_span_alignment =
-            ((self.attention_span / other.attention_span) as f32).min(1.0) as f32;
+        // Traits without an attention span or any communicability don't require them, so
+        // any learner is fully aligned with them, rather than dividing by zero.
+        let attention_span_alignment = if other.attention_span > 0 {
+            (self.attention_span as f32 / other.attention_span as f32).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
 
         let equal_communicability = self
             .communicability
@@ -51,7 +61,11 @@ impl ASDTraitComparison for ASDTraits {
             .filter(|&c| other.communicability.contains(c))
             .count() as f32;
 
-        let communicability_alignment = equal_communicability / other.communicability.len() as f32;
+        let communicability_alignment = if other.communicability.is_empty() {
+            1.0
+        } else {
+            equal_communicability / other.communicability.len() as f32
+        };
         let communication_level_alignment = match self.communication_level {
             CommunicationLevel::High => match other.communication_level {
                 CommunicationLevel::High => 1.0,
@@ -110,12 +124,10 @@ impl ASDTraitComparison for ASDTraits {
         let weight_motor_skills = 0.2;
 
         // Calculate overall alignment score
-        let overall_alignment = attention_span_alignment * weight_attention_span
+        attention_span_alignment * weight_attention_span
             + communicability_alignment * weight_communicability
             + communication_level_alignment * weight_communication_level
-            + motor_skills_alignment * weight_motor_skills;
-
-        overall_alignment
+            + motor_skills_alignment * weight_motor_skills
     }
 }
 
@@ -162,6 +174,7 @@ impl ASDTraits {
 /// lesson plans that they are working on. They also have a unique set of measurements
 /// for their ASD traits.
 #[derive(Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Learner {
     id: String,
     name: String,
@@ -204,11 +217,21 @@ impl Learner {
         &self.lesson_plans
     }
 
-    pub fn get_current_lesson(&self) -> &Lesson {
+    pub fn get_current_lesson(&self) -> Result<&Lesson> {
         // get the last (latest) lesson plan
-        let latest_plan = self.lesson_plans.last().unwrap();
+        let latest_plan =
+            self.lesson_plans
+                .last()
+                .ok_or_else(|| NeuroNudgeError::NoLessonPlan {
+                    learner_id: self.id.clone(),
+                })?;
         // for now, all plans have 1 lesson in them. return it
-        &latest_plan.get_lessons()[0]
+        latest_plan
+            .get_lessons()
+            .first()
+            .ok_or_else(|| NeuroNudgeError::EmptyLessonPlan {
+                lesson_plan_id: latest_plan.get_id().clone(),
+            })
     }
 
     pub fn get_id(&self) -> &String {
@@ -231,3 +254,34 @@ impl Learner {
         &self.q_table_id
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn traits(attention_span: i32, communicability: Vec<Communicability>) -> ASDTraits {
+        ASDTraits::new(
+            "Learner".to_string(),
+            attention_span,
+            communicability,
+            CommunicationLevel::High,
+            MotorSkills::VeryHigh,
+        )
+    }
+
+    #[test]
+    fn attention_span_alignment_is_a_fraction() {
+        let learner = traits(5, vec![Communicability::Verbal]);
+        let question = traits(10, vec![Communicability::Verbal]);
+        assert_eq!(learner.calculate_alignment(&question), 0.8);
+        assert_eq!(question.calculate_alignment(&learner), 1.0);
+    }
+
+    #[test]
+    fn traits_without_requirements_are_fully_aligned() {
+        let learner = traits(5, vec![Communicability::Verbal]);
+        let question = traits(0, vec![]);
+        assert_eq!(learner.calculate_alignment(&question), 1.0);
+        assert_eq!(learner.calculate_alignment(&traits(-3, vec![])), 1.0);
+    }
+}
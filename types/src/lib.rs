@@ -1,5 +1,18 @@
 //! This module defines the types used by the recommendation engine, NeuroNudge.
+//!
+//! Enable the `serde` feature to make every content, learner and engine type
+//! serializable with serde, to save/restore q table snapshots, to load an
+//! `EngineConfig` from a file and to load `ContentModule`s from content files.
 
+pub mod config;
 pub mod content;
+#[cfg(feature = "serde")]
+pub mod content_file;
 pub mod engine;
+pub mod error;
 pub mod learner;
+pub mod prerequisites;
+pub mod profile;
+#[cfg(feature = "serde")]
+pub mod snapshot;
+pub mod validation;
@@ -0,0 +1,123 @@
+//! This defines the prerequisites between lessons, and between modules.
+//!
+//! A lesson can require other lessons of its module to be mastered first, e.g.
+//! "Recognising Circles" before "Tracing Circles", and a module can require other
+//! modules, e.g. Shapes before Colours and Shapes. Prerequisites must form a directed
+//! acyclic graph, which is checked when content is loaded, as a cycle would leave every
+//! lesson in it locked forever.
+//!
+//! The engine only recommends a lesson once all of its prerequisites reach the
+//! configured `prerequisite_mastery` (see `QTableAlgorithm::is_lesson_unlocked`), and
+//! `LearnerProfile::available_modules` gives the modules whose prerequisite modules
+//! have been mastered.
+//!
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::content::{ContentModule, Lesson};
+use crate::error::{NeuroNudgeError, Result};
+
+/// PrerequisiteGraph
+/// The prerequisites of some lessons or modules, by their ids.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct PrerequisiteGraph {
+    /// Kept in id order, so that the same graph always reports the same cycle.
+    prerequisites: BTreeMap<String, Vec<String>>,
+}
+
+/// How far the search for a cycle has got with some node.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    InProgress,
+    Done,
+}
+
+impl PrerequisiteGraph {
+    pub fn new() -> PrerequisiteGraph {
+        PrerequisiteGraph::default()
+    }
+
+    /// Build the graph of the prerequisites between some lessons.
+    pub fn for_lessons(lessons: &[Lesson]) -> PrerequisiteGraph {
+        let mut graph = PrerequisiteGraph::new();
+        for lesson in lessons {
+            graph.add(lesson.get_id(), lesson.get_prerequisites());
+        }
+        graph
+    }
+
+    /// Build the graph of the prerequisites between some modules.
+    pub fn for_modules(modules: &[ContentModule]) -> PrerequisiteGraph {
+        let mut graph = PrerequisiteGraph::new();
+        for module in modules {
+            graph.add(module.get_id(), module.get_prerequisites());
+        }
+        graph
+    }
+
+    pub fn add(&mut self, id: &str, prerequisites: &[String]) {
+        self.prerequisites
+            .entry(id.to_string())
+            .or_default()
+            .extend(prerequisites.iter().cloned());
+    }
+
+    pub fn get_prerequisites(&self, id: &str) -> &[String] {
+        self.prerequisites.get(id).map_or(&[], |p| p.as_slice())
+    }
+
+    /// Get every (id, prerequisite) where the prerequisite isn't in the graph.
+    pub fn missing_prerequisites(&self) -> Vec<(&String, &String)> {
+        self.prerequisites
+            .iter()
+            .flat_map(|(id, prerequisites)| prerequisites.iter().map(move |p| (id, p)))
+            .filter(|(_, prerequisite)| !self.prerequisites.contains_key(*prerequisite))
+            .collect()
+    }
+
+    /// Find a cycle in the graph, as the ids along it from and back to its first id.
+    pub fn find_cycle(&self) -> Option<Vec<String>> {
+        let mut visits = HashMap::new();
+        let mut path = vec![];
+        self.prerequisites
+            .keys()
+            .find_map(|id| self.find_cycle_from(id, &mut visits, &mut path))
+    }
+
+    fn find_cycle_from<'a>(
+        &'a self,
+        id: &'a String,
+        visits: &mut HashMap<&'a String, Visit>,
+        path: &mut Vec<&'a String>,
+    ) -> Option<Vec<String>> {
+        match visits.get(id) {
+            Some(Visit::Done) => return None,
+            Some(Visit::InProgress) => {
+                let start = path.iter().position(|&p| p == id).unwrap_or(0);
+                let mut cycle: Vec<String> = path[start..].iter().map(|&p| p.clone()).collect();
+                cycle.push(id.clone());
+                return Some(cycle);
+            }
+            None => {}
+        }
+
+        visits.insert(id, Visit::InProgress);
+        path.push(id);
+        for prerequisite in self.prerequisites.get(id).into_iter().flatten() {
+            if let Some(cycle) = self.find_cycle_from(prerequisite, visits, path) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        visits.insert(id, Visit::Done);
+        None
+    }
+
+    /// Check that the graph has no cycles.
+    pub fn check_acyclic(&self) -> Result<()> {
+        match self.find_cycle() {
+            Some(cycle) => Err(NeuroNudgeError::PrerequisiteCycle(cycle)),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,325 @@
+//! This defines the learner profile, which is the q table map described in the
+//! `engine` module.
+//!
+//! A learner has a q table for each module of content that they do. The profile holds
+//! these q tables keyed by module id, creating a q table the first time the learner
+//! starts a module. This way one learner can work through several modules side by
+//! side, with their progress in each tracked separately.
+//!
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use crate::config::EngineConfig;
+use crate::content::{ContentModule, DifficultyLevel, LessonCatalogue, DIFFICULTY_LEVELS};
+use crate::engine::{Mastery, QTableAlgorithm, Strategy};
+use crate::error::Result;
+
+/// LearnerProfile
+/// A learner profile maps each module that a learner has started to their q table
+/// for that module. New q tables use the profile's epsilon, strategy and engine config.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LearnerProfile {
+    learner_id: String,
+    epsilon: f32,
+    strategy: Strategy,
+    config: EngineConfig,
+    /// The q table of each module, by module id.
+    q_tables: HashMap<String, QTableAlgorithm>,
+}
+
+/// ModuleProgress
+/// A summary of a learner's progress through a module, derived from their q table for
+/// that module.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModuleProgress {
+    module_id: String,
+    q_table_id: String,
+    /// The best q value and mastery of each difficulty level, from easiest to hardest.
+    levels: Vec<(DifficultyLevel, f32, Mastery)>,
+    /// The hardest difficulty level that has been fully mastered, if any.
+    highest_mastered_level: Option<DifficultyLevel>,
+    /// The hardest difficulty level that has been attempted, if any.
+    highest_attempted_level: Option<DifficultyLevel>,
+    total_lessons_attempted: u64,
+}
+
+impl ModuleProgress {
+    /// Summarise the progress held in a q table for some module.
+    pub fn new(module_id: &str, q_table: &QTableAlgorithm) -> ModuleProgress {
+        let levels: Vec<(DifficultyLevel, f32, Mastery)> = DIFFICULTY_LEVELS
+            .iter()
+            .map(|d| {
+                (
+                    d.clone(),
+                    q_table.get_best_value_for_difficulty(d),
+                    q_table.get_mastery_for_difficulty(d),
+                )
+            })
+            .collect();
+
+        let highest_mastered_level = levels
+            .iter()
+            .rev()
+            .find(|(_, _, mastery)| mastery == &Mastery::Full)
+            .map(|(d, _, _)| d.clone());
+
+        let highest_attempted_level = DIFFICULTY_LEVELS
+            .iter()
+            .rev()
+            .find(|d| q_table.has_attempted_difficulty(d))
+            .cloned();
+
+        ModuleProgress {
+            module_id: module_id.to_string(),
+            q_table_id: q_table.get_id().to_string(),
+            levels,
+            highest_mastered_level,
+            highest_attempted_level,
+            total_lessons_attempted: q_table.get_total_updates(),
+        }
+    }
+
+    pub fn get_module_id(&self) -> &String {
+        &self.module_id
+    }
+
+    pub fn get_q_table_id(&self) -> &String {
+        &self.q_table_id
+    }
+
+    pub fn get_levels(&self) -> &Vec<(DifficultyLevel, f32, Mastery)> {
+        &self.levels
+    }
+
+    pub fn get_highest_mastered_level(&self) -> &Option<DifficultyLevel> {
+        &self.highest_mastered_level
+    }
+
+    pub fn get_highest_attempted_level(&self) -> &Option<DifficultyLevel> {
+        &self.highest_attempted_level
+    }
+
+    pub fn get_total_lessons_attempted(&self) -> u64 {
+        self.total_lessons_attempted
+    }
+}
+
+impl LearnerProfile {
+    pub fn new(
+        learner_id: String,
+        epsilon: f32,
+        strategy: Strategy,
+        config: EngineConfig,
+    ) -> LearnerProfile {
+        LearnerProfile {
+            learner_id,
+            epsilon,
+            strategy,
+            config,
+            q_tables: HashMap::new(),
+        }
+    }
+
+    pub fn get_learner_id(&self) -> &String {
+        &self.learner_id
+    }
+
+    /// Get the learner's q table for some module, creating it if this is the first
+    /// time the learner has started the module. A new q table has an entry with a
+    /// value of 0 for every lesson in the module, is for the module's current content
+    /// version, and has the prerequisites of the module's lessons. Fails if those
+    /// prerequisites aren't lessons of the module or form a cycle, or if the profile's
+    /// config is invalid.
+    pub fn get_or_create_q_table(
+        &mut self,
+        module: &ContentModule,
+    ) -> Result<&mut QTableAlgorithm> {
+        match self.q_tables.entry(module.get_id().clone()) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                let mut q_table = QTableAlgorithm::new(
+                    None,
+                    self.epsilon,
+                    self.strategy.clone(),
+                    self.config.clone(),
+                )?
+                .with_content_version(module.get_version());
+                for lesson in module.get_lessons() {
+                    q_table.insert(
+                        (
+                            lesson.get_id().clone(),
+                            lesson.get_difficulty_level().clone(),
+                        ),
+                        0.0,
+                    );
+                }
+                q_table.set_lesson_prerequisites(&LessonCatalogue::new(
+                    module.get_lessons().clone(),
+                ))?;
+                Ok(entry.insert(q_table))
+            }
+        }
+    }
+
+    pub fn get_q_table(&self, module_id: &str) -> Option<&QTableAlgorithm> {
+        self.q_tables.get(module_id)
+    }
+
+    pub fn get_q_table_mut(&mut self, module_id: &str) -> Option<&mut QTableAlgorithm> {
+        self.q_tables.get_mut(module_id)
+    }
+
+    /// Check if the learner has started some module, i.e. has a q table for it.
+    pub fn has_started_module(&self, module_id: &str) -> bool {
+        self.q_tables.contains_key(module_id)
+    }
+
+    /// Get the ids of all modules that the learner has started, in sorted order.
+    pub fn get_module_ids(&self) -> Vec<&String> {
+        let mut module_ids: Vec<&String> = self.q_tables.keys().collect();
+        module_ids.sort();
+        module_ids
+    }
+
+    /// Get the learner's progress in some module, if they have started it.
+    pub fn get_module_progress(&self, module_id: &str) -> Option<ModuleProgress> {
+        self.q_tables
+            .get(module_id)
+            .map(|q_table| ModuleProgress::new(module_id, q_table))
+    }
+
+    /// Get the modules that the learner can be offered - those whose prerequisite
+    /// modules all reach the configured `prerequisite_mastery`, going by the learner's
+    /// q table for each (see `QTableAlgorithm::get_module_mastery`). A module that the
+    /// learner hasn't started has no mastery.
+    pub fn available_modules<'a>(&self, modules: &'a [ContentModule]) -> Vec<&'a ContentModule> {
+        modules
+            .iter()
+            .filter(|module| {
+                module.get_prerequisites().iter().all(|prerequisite| {
+                    self.q_tables
+                        .get(prerequisite)
+                        .map_or(Mastery::None, |q_table| q_table.get_module_mastery())
+                        >= self.config.prerequisite_mastery
+                })
+            })
+            .collect()
+    }
+
+    /// Get the learner's progress in every module that they have started, in order of
+    /// module id.
+    pub fn get_progress(&self) -> Vec<ModuleProgress> {
+        self.get_module_ids()
+            .into_iter()
+            .filter_map(|module_id| self.get_module_progress(module_id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::{Answer, ContentMigration, Lesson, Prompt, PromptType, Question};
+
+    fn lesson(name: &str, difficulty_level: DifficultyLevel, module_id: &str) -> Lesson {
+        let question = Question::new(
+            Prompt::new(PromptType::Text, name.to_string()),
+            None,
+            None,
+            Answer::Boolean(true),
+            None,
+        );
+        Lesson::new(
+            name.to_string(),
+            vec![question],
+            difficulty_level,
+            module_id.to_string(),
+        )
+    }
+
+    fn profile() -> LearnerProfile {
+        LearnerProfile::new(
+            "Learner".to_string(),
+            0.1,
+            Strategy::BaseQLearning,
+            EngineConfig::default(),
+        )
+    }
+
+    #[test]
+    fn q_table_for_a_versioned_module_can_be_migrated() {
+        let mut module = ContentModule::new("Shapes".to_string()).with_version(2);
+        let old_lesson = lesson("Circles", DifficultyLevel::VeryEasy, module.get_id());
+        module.add_lesson(old_lesson.clone());
+
+        let mut profile = profile();
+        let q_table = profile.get_or_create_q_table(&module).unwrap();
+        assert_eq!(q_table.get_content_version(), 2);
+        q_table.insert(
+            (old_lesson.get_id().clone(), DifficultyLevel::VeryEasy),
+            0.5,
+        );
+
+        let new_lesson = lesson("Round Shapes", DifficultyLevel::VeryEasy, module.get_id());
+        let migration = ContentMigration::new(2, 3)
+            .with_mapping(old_lesson.get_id().clone(), new_lesson.get_id().clone());
+        let new_lessons = LessonCatalogue::new(vec![new_lesson.clone()]);
+        q_table.migrate(&migration, &new_lessons).unwrap();
+
+        assert_eq!(q_table.get_content_version(), 3);
+        assert_eq!(
+            q_table.get(&(new_lesson.get_id().clone(), DifficultyLevel::VeryEasy)),
+            Some(&0.5)
+        );
+        assert_eq!(
+            q_table.get(&(old_lesson.get_id().clone(), DifficultyLevel::VeryEasy)),
+            None
+        );
+    }
+
+    #[test]
+    fn q_table_has_the_prerequisites_of_the_module_lessons() {
+        let mut module = ContentModule::new("Shapes".to_string());
+        let circles = lesson("Circles", DifficultyLevel::VeryEasy, module.get_id());
+        let squares = lesson("Squares", DifficultyLevel::VeryEasy, module.get_id())
+            .with_prerequisites(vec![circles.get_id().clone()]);
+        module.add_lesson(circles.clone());
+        module.add_lesson(squares.clone());
+
+        let mut profile = profile();
+        let q_table = profile.get_or_create_q_table(&module).unwrap();
+        assert!(q_table.is_lesson_unlocked(circles.get_id()));
+        assert!(!q_table.is_lesson_unlocked(squares.get_id()));
+
+        q_table.insert((circles.get_id().clone(), DifficultyLevel::VeryEasy), 1.0);
+        assert!(q_table.is_lesson_unlocked(squares.get_id()));
+    }
+
+    #[test]
+    fn modules_are_available_once_their_prerequisites_are_mastered() {
+        let mut shapes = ContentModule::new("Shapes".to_string());
+        let circles = lesson("Circles", DifficultyLevel::VeryEasy, shapes.get_id());
+        shapes.add_lesson(circles.clone());
+        let mut colours = ContentModule::new("Colours and Shapes".to_string())
+            .with_prerequisites(vec![shapes.get_id().clone()]);
+        colours.add_lesson(lesson(
+            "Red Circles",
+            DifficultyLevel::VeryEasy,
+            colours.get_id(),
+        ));
+        let modules = vec![shapes.clone(), colours.clone()];
+
+        let mut profile = profile();
+        assert_eq!(profile.available_modules(&modules), vec![&shapes]);
+
+        profile.get_or_create_q_table(&shapes).unwrap();
+        assert_eq!(profile.available_modules(&modules), vec![&shapes]);
+
+        let q_table = profile.get_q_table_mut(shapes.get_id()).unwrap();
+        q_table.insert((circles.get_id().clone(), DifficultyLevel::VeryEasy), 1.0);
+        assert_eq!(profile.available_modules(&modules), vec![&shapes, &colours]);
+    }
+}
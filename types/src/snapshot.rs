@@ -0,0 +1,222 @@
+//! This defines snapshots of a learner's q table, so that their progress can be
+//! persisted between sessions.
+//!
+//! A snapshot captures the complete state of a QTableAlgorithm - the q values, the
+//! decay and attempt counters, the strategy, epsilon and hyperparameters - so that a
+//! restored algorithm carries on exactly where the saved one left off.
+//!
+//! Snapshots are stored as JSON files named after the q table id, which is the id
+//! that a `Learner` holds in `q_table_id`. So a learner's q table can be found in a
+//! snapshot directory from the learner alone.
+//!
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::QTableAlgorithm;
+use crate::error::{NeuroNudgeError, Result};
+use crate::learner::Learner;
+
+/// Version of the snapshot file format. Once released, this must be bumped whenever
+/// the serialized shape of QTableAlgorithm changes, so that stale snapshots are
+/// rejected with `UnsupportedSnapshotVersion` rather than restored incorrectly.
+/// Snapshots are never migrated between format versions - a learner's progress is
+/// carried across content changes with a `ContentMigration` instead, which works on a
+/// restored q table at the current format.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct QTableSnapshotRef<'a> {
+    version: u32,
+    algorithm: &'a QTableAlgorithm,
+}
+
+#[derive(Deserialize)]
+struct QTableSnapshot {
+    version: u32,
+    algorithm: QTableAlgorithm,
+}
+
+impl QTableAlgorithm {
+    /// Get the path of the snapshot for some q table id within a snapshot directory.
+    /// Fails if the id is empty or could name a path outside the directory, i.e. it
+    /// contains a path separator or is `.` or `..`.
+    pub fn snapshot_path(directory: &Path, q_table_id: &str) -> Result<PathBuf> {
+        if q_table_id.is_empty()
+            || q_table_id == "."
+            || q_table_id == ".."
+            || q_table_id.contains(['/', '\\'])
+        {
+            return Err(NeuroNudgeError::InvalidSnapshotId(q_table_id.to_string()));
+        }
+        Ok(directory.join(format!("{}.json", q_table_id)))
+    }
+
+    /// Serialize the complete state of the algorithm into a versioned snapshot.
+    pub fn to_snapshot_string(&self) -> Result<String> {
+        let snapshot = QTableSnapshotRef {
+            version: SNAPSHOT_VERSION,
+            algorithm: self,
+        };
+        Ok(serde_json::to_string_pretty(&snapshot)?)
+    }
+
+    /// Restore an algorithm from a versioned snapshot. Snapshots written with a
+    /// different format version, with a config that doesn't validate, or with lesson
+    /// prerequisites that form a cycle are rejected.
+    pub fn from_snapshot_str(snapshot: &str) -> Result<QTableAlgorithm> {
+        let snapshot: QTableSnapshot = serde_json::from_str(snapshot)?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(NeuroNudgeError::UnsupportedSnapshotVersion {
+                found: snapshot.version,
+                expected: SNAPSHOT_VERSION,
+            });
+        }
+        snapshot.algorithm.get_config().validate()?;
+        snapshot.algorithm.check_lesson_prerequisites()?;
+        Ok(snapshot.algorithm)
+    }
+
+    /// Save a snapshot of the algorithm into some directory, returning the path of
+    /// the snapshot file. The file is written to a temporary path first and then
+    /// renamed, so an interrupted save never leaves a half written snapshot behind.
+    pub fn save_snapshot(&self, directory: &Path) -> Result<PathBuf> {
+        fs::create_dir_all(directory)?;
+        let path = QTableAlgorithm::snapshot_path(directory, self.get_id())?;
+        let temporary_path = path.with_extension("json.tmp");
+        fs::write(&temporary_path, self.to_snapshot_string()?)?;
+        fs::rename(&temporary_path, &path)?;
+        Ok(path)
+    }
+
+    /// Load the snapshot of some q table id from a snapshot directory.
+    pub fn load_snapshot(directory: &Path, q_table_id: &str) -> Result<QTableAlgorithm> {
+        let path = QTableAlgorithm::snapshot_path(directory, q_table_id)?;
+        QTableAlgorithm::from_snapshot_str(&fs::read_to_string(path)?)
+    }
+
+    /// Load the q table of a learner from a snapshot directory, using their q table id.
+    pub fn load_snapshot_for_learner(
+        directory: &Path,
+        learner: &Learner,
+    ) -> Result<QTableAlgorithm> {
+        QTableAlgorithm::load_snapshot(directory, learner.get_q_table_id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EngineConfig;
+    use crate::content::DifficultyLevel;
+    use crate::engine::Strategy;
+
+    fn q_table() -> QTableAlgorithm {
+        let mut q_table = QTableAlgorithm::new(
+            None,
+            0.2,
+            Strategy::DecayingQValues,
+            EngineConfig::default(),
+        )
+        .unwrap();
+        q_table.insert(("circles".to_string(), DifficultyLevel::VeryEasy), 0.75);
+        q_table.insert(("squares".to_string(), DifficultyLevel::Easy), 0.25);
+        q_table
+    }
+
+    /// A new, empty directory to save snapshots into.
+    fn snapshot_directory() -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("neuro-nudge-snapshots-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn snapshot_string_round_trips() {
+        let q_table = q_table();
+        let snapshot = q_table.to_snapshot_string().unwrap();
+        assert_eq!(
+            QTableAlgorithm::from_snapshot_str(&snapshot).unwrap(),
+            q_table
+        );
+    }
+
+    #[test]
+    fn snapshot_is_saved_and_loaded_by_q_table_id() {
+        let directory = snapshot_directory();
+        let q_table = q_table();
+
+        let path = q_table.save_snapshot(&directory).unwrap();
+        assert_eq!(path, directory.join(format!("{}.json", q_table.get_id())));
+        // The temporary file is renamed into place, so nothing else is left behind.
+        let files: Vec<PathBuf> = fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(files, vec![path]);
+
+        let loaded = QTableAlgorithm::load_snapshot(&directory, q_table.get_id()).unwrap();
+        assert_eq!(loaded, q_table);
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn snapshot_of_another_version_is_rejected() {
+        let mut snapshot: serde_json::Value =
+            serde_json::from_str(&q_table().to_snapshot_string().unwrap()).unwrap();
+        snapshot["version"] = serde_json::json!(SNAPSHOT_VERSION + 1);
+
+        let error = QTableAlgorithm::from_snapshot_str(&snapshot.to_string()).unwrap_err();
+        assert!(matches!(
+            error,
+            NeuroNudgeError::UnsupportedSnapshotVersion { found, expected }
+                if found == SNAPSHOT_VERSION + 1 && expected == SNAPSHOT_VERSION
+        ));
+    }
+
+    #[test]
+    fn snapshot_with_an_invalid_config_is_rejected() {
+        let mut snapshot: serde_json::Value =
+            serde_json::from_str(&q_table().to_snapshot_string().unwrap()).unwrap();
+        snapshot["algorithm"]["config"]["learning_rate"] = serde_json::json!(0.0);
+
+        let error = QTableAlgorithm::from_snapshot_str(&snapshot.to_string()).unwrap_err();
+        assert!(matches!(error, NeuroNudgeError::InvalidConfig(_)));
+    }
+
+    #[test]
+    fn snapshot_with_cyclic_lesson_prerequisites_is_rejected() {
+        let mut snapshot: serde_json::Value =
+            serde_json::from_str(&q_table().to_snapshot_string().unwrap()).unwrap();
+        snapshot["algorithm"]["lesson_prerequisites"] = serde_json::json!({
+            "circles": ["squares"],
+            "squares": ["circles"],
+        });
+
+        let error = QTableAlgorithm::from_snapshot_str(&snapshot.to_string()).unwrap_err();
+        assert!(matches!(error, NeuroNudgeError::PrerequisiteCycle(_)));
+    }
+
+    #[test]
+    fn q_table_ids_that_escape_the_directory_are_rejected() {
+        let directory = Path::new("snapshots");
+        for q_table_id in ["", ".", "..", "../../etc/x", "/etc/x", "a/b", "a\\b"] {
+            assert!(
+                matches!(
+                    QTableAlgorithm::snapshot_path(directory, q_table_id),
+                    Err(NeuroNudgeError::InvalidSnapshotId(_))
+                ),
+                "{:?} was accepted",
+                q_table_id
+            );
+            assert!(QTableAlgorithm::load_snapshot(directory, q_table_id).is_err());
+        }
+        assert_eq!(
+            QTableAlgorithm::snapshot_path(directory, "learner-1..2").unwrap(),
+            directory.join("learner-1..2.json")
+        );
+    }
+}
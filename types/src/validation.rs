@@ -0,0 +1,312 @@
+//! This defines the validation of content modules, to catch inconsistencies in a
+//! curriculum before any learner is given it.
+//!
+//! `ContentModule::validate` checks every lesson and question of a module, and returns
+//! the issues it finds as errors or warnings. Errors are content that the engine can't
+//! use - a lesson without questions, a question whose answer doesn't make sense with
+//! its options, question ASD traits that learners can't be compared against, or a gap
+//! in the module's difficulty levels that learners can't progress past. Warnings are
+//! content that works but is likely a mistake, such as a multiple choice question with
+//! only one option.
+//!
+//! Lesson prerequisites must be lessons of the same module and must not form a cycle,
+//! or the lessons depending on them could never be unlocked.
+//!
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::content::{Answer, ContentModule, DifficultyLevel, Lesson, DIFFICULTY_LEVELS};
+use crate::error::NeuroNudgeError;
+use crate::prerequisites::PrerequisiteGraph;
+
+/// Severity
+/// How serious an issue with some content is.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    /// Content that the engine can't use.
+    Error,
+    /// Content that the engine can use, but is likely a mistake.
+    Warning,
+}
+
+/// ContentIssue
+/// An issue found in a module, with where in the module it was found, e.g.
+/// `lessons[1].questions[0]`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContentIssue {
+    pub severity: Severity,
+    pub field: String,
+    pub message: String,
+}
+
+/// ContentValidation
+/// Every issue found in a module.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContentValidation {
+    pub issues: Vec<ContentIssue>,
+}
+
+impl fmt::Display for ContentIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        if self.field.is_empty() {
+            write!(f, "{}: {}", severity, self.message)
+        } else {
+            write!(f, "{} at {}: {}", severity, self.field, self.message)
+        }
+    }
+}
+
+impl ContentValidation {
+    fn add(&mut self, severity: Severity, field: String, message: String) {
+        self.issues.push(ContentIssue {
+            severity,
+            field,
+            message,
+        });
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &ContentIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &ContentIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Warning)
+    }
+
+    /// Whether the module has no errors. It may still have warnings.
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+}
+
+impl ContentModule {
+    /// Check the module's lessons and questions for inconsistencies, returning every
+    /// error and warning found.
+    pub fn validate(&self) -> ContentValidation {
+        let mut validation = ContentValidation::default();
+
+        if self.get_lessons().is_empty() {
+            validation.add(
+                Severity::Error,
+                "lessons".to_string(),
+                "the module has no lessons".to_string(),
+            );
+            return validation;
+        }
+
+        let mut lesson_ids = HashSet::new();
+        for (lesson_index, lesson) in self.get_lessons().iter().enumerate() {
+            let field = format!("lessons[{}]", lesson_index);
+            if !lesson_ids.insert(lesson.get_id()) {
+                validation.add(
+                    Severity::Error,
+                    field.clone(),
+                    format!(
+                        "lesson \"{}\" has the same id as an earlier lesson, {}",
+                        lesson.get_name(),
+                        lesson.get_id()
+                    ),
+                );
+            }
+            validate_lesson(lesson, &field, &mut validation);
+        }
+
+        validate_difficulty_levels(self, &mut validation);
+        validate_prerequisites(self, &mut validation);
+        validation
+    }
+}
+
+fn validate_lesson(lesson: &Lesson, field: &str, validation: &mut ContentValidation) {
+    if lesson.get_questions().is_empty() {
+        validation.add(
+            Severity::Error,
+            field.to_string(),
+            format!("lesson \"{}\" has no questions", lesson.get_name()),
+        );
+        return;
+    }
+
+    let mut question_ids = HashSet::new();
+    for (question_index, question) in lesson.get_questions().iter().enumerate() {
+        let field = format!("{}.questions[{}]", field, question_index);
+        if !question_ids.insert(question.get_id()) {
+            validation.add(
+                Severity::Error,
+                field.clone(),
+                format!(
+                    "the question has the same id as an earlier question, {}",
+                    question.get_id()
+                ),
+            );
+        }
+
+        match question.validate() {
+            Err(NeuroNudgeError::InvalidQuestion { reason, .. }) => {
+                validation.add(Severity::Error, field, reason);
+                continue;
+            }
+            Err(error) => {
+                validation.add(Severity::Error, field, error.to_string());
+                continue;
+            }
+            Ok(()) => {}
+        }
+
+        // Learners' traits are compared to the question's as ratios, which need an
+        // attention span and communicability to divide by.
+        if let Some(asd_traits) = question.get_asd_traits_parameters() {
+            if *asd_traits.get_attention_span() < 1 {
+                validation.add(
+                    Severity::Error,
+                    format!("{}.asd_traits.attention_span", field),
+                    format!(
+                        "the attention span must be at least 1, but is {}",
+                        asd_traits.get_attention_span()
+                    ),
+                );
+            }
+            if asd_traits.get_communicability().is_empty() {
+                validation.add(
+                    Severity::Error,
+                    format!("{}.asd_traits.communicability", field),
+                    "the question's ASD traits have no communicability".to_string(),
+                );
+            }
+        }
+
+        let total_options = question.get_options().as_ref().map_or(0, |o| o.len());
+        let picks_an_option = matches!(
+            question.get_answer(),
+            Answer::Integer(_) | Answer::MultiSelect(_) | Answer::Ordering(_)
+        );
+        if picks_an_option && total_options < 2 {
+            validation.add(
+                Severity::Warning,
+                field,
+                "the question has only one option, so it can't be answered incorrectly".to_string(),
+            );
+        }
+    }
+
+    // The lesson's ASD traits are those of its first question, so they are only
+    // representative if every question has the same ones.
+    let first_asd_traits = lesson.get_questions()[0].get_asd_traits_parameters();
+    if lesson
+        .get_questions()
+        .iter()
+        .any(|question| question.get_asd_traits_parameters() != first_asd_traits)
+    {
+        validation.add(
+            Severity::Warning,
+            field.to_string(),
+            format!(
+                "the questions of lesson \"{}\" have different ASD trait requirements",
+                lesson.get_name()
+            ),
+        );
+    }
+}
+
+/// Check that the module's lessons cover a run of difficulty levels without any gaps,
+/// as the engine only moves learners up or down one level at a time.
+fn validate_difficulty_levels(module: &ContentModule, validation: &mut ContentValidation) {
+    let covered: Vec<bool> = DIFFICULTY_LEVELS
+        .iter()
+        .map(|difficulty_level| {
+            module
+                .get_lessons()
+                .iter()
+                .any(|lesson| lesson.get_difficulty_level() == difficulty_level)
+        })
+        .collect();
+    let lowest = covered.iter().position(|&c| c).unwrap_or(0);
+    let highest = covered.iter().rposition(|&c| c).unwrap_or(0);
+    let level_names = |levels: &[DifficultyLevel]| {
+        levels
+            .iter()
+            .map(|level| level.clone().into())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    };
+
+    let skipped: Vec<DifficultyLevel> = (lowest..=highest)
+        .filter(|&index| !covered[index])
+        .map(|index| DIFFICULTY_LEVELS[index].clone())
+        .collect();
+    if !skipped.is_empty() {
+        validation.add(
+            Severity::Error,
+            "lessons".to_string(),
+            format!(
+                "the module skips difficulty levels {}, so learners can't progress past them",
+                level_names(&skipped)
+            ),
+        );
+    }
+
+    let uncovered: Vec<DifficultyLevel> = (0..lowest)
+        .chain(highest + 1..DIFFICULTY_LEVELS.len())
+        .map(|index| DIFFICULTY_LEVELS[index].clone())
+        .collect();
+    if !uncovered.is_empty() {
+        validation.add(
+            Severity::Warning,
+            "lessons".to_string(),
+            format!(
+                "the module has no lessons at difficulty levels {}",
+                level_names(&uncovered)
+            ),
+        );
+    }
+}
+
+/// Check that every lesson prerequisite is a lesson of the module, and that they don't
+/// form a cycle.
+fn validate_prerequisites(module: &ContentModule, validation: &mut ContentValidation) {
+    let lessons = module.get_lessons();
+    let graph = PrerequisiteGraph::for_lessons(lessons);
+    let lesson_name = |id: &str| {
+        lessons
+            .iter()
+            .find(|lesson| lesson.get_id() == id)
+            .map_or(id.to_string(), |lesson| lesson.get_name().clone())
+    };
+
+    for (id, prerequisite) in graph.missing_prerequisites() {
+        let lesson_index = lessons
+            .iter()
+            .position(|lesson| lesson.get_id() == id)
+            .unwrap_or(0);
+        validation.add(
+            Severity::Error,
+            format!("lessons[{}].prerequisites", lesson_index),
+            format!(
+                "lesson \"{}\" requires {}, which isn't a lesson of the module",
+                lesson_name(id),
+                prerequisite
+            ),
+        );
+    }
+
+    if let Some(cycle) = graph.find_cycle() {
+        let names: Vec<String> = cycle.iter().map(|id| lesson_name(id)).collect();
+        validation.add(
+            Severity::Error,
+            "lessons".to_string(),
+            format!("prerequisites form a cycle: {}", names.join(" -> ")),
+        );
+    }
+}